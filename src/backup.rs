@@ -0,0 +1,185 @@
+use crate::{movie::Movie, nfo::Nfo, work::Work};
+use std::collections::{BTreeMap, HashMap};
+
+/// A backup drive, in GB
+#[derive(Debug)]
+pub struct Drive {
+    pub capacity: f32,
+    pub used: f32,
+}
+
+impl Drive {
+    pub fn free(&self) -> f32 {
+        self.capacity - self.used
+    }
+}
+
+/// Letterboxd rating as a 1-10 score, else the rating from the movie's nfo
+fn score(movie: &Movie) -> f32 {
+    movie
+        .rating
+        .as_deref()
+        .and_then(Nfo::userrating)
+        .map(f32::from)
+        .or(movie.user_rating)
+        .unwrap_or_default()
+}
+
+/// The drive a group of this size goes on: its collection's drive if it still
+/// fits there, otherwise the one with the most free space, to spread the library
+fn pick(drives: &[Drive], size: f32, home: Option<usize>) -> Option<usize> {
+    if let Some(home) = home.filter(|d| drives[*d].free() >= size) {
+        return Some(home);
+    }
+    (0..drives.len())
+        .filter(|d| drives[*d].free() >= size)
+        .max_by(|a, b| drives[*a].free().total_cmp(&drives[*b].free()))
+}
+
+/// Place films on drives. Versions of one work, and films of one collection, are kept
+/// on one drive where they fit; collections go where the rest of them already is (`homes`).
+/// Large groups go first so they still fit; with `by_rating`, the highest rated
+/// go first instead, so films that do not fit are the lowest rated ones.
+/// Returns the drive of each placed film, and the films that did not fit.
+pub fn place<'a>(
+    drives: &mut [Drive],
+    mut homes: HashMap<String, usize>,
+    films: Vec<&'a Movie>,
+    by_rating: bool,
+) -> (Vec<(&'a Movie, usize)>, Vec<&'a Movie>) {
+    let mut works: BTreeMap<String, Vec<&Movie>> = BTreeMap::new();
+    let mut collections: BTreeMap<&str, Vec<&Movie>> = BTreeMap::new();
+    for movie in films {
+        match movie.collection.as_deref() {
+            Some(c) => collections.entry(c).or_default().push(movie),
+            None => works.entry(Work::key(movie)).or_default().push(movie),
+        }
+    }
+    let mut groups = works.into_values().collect::<Vec<_>>();
+    groups.extend(collections.into_values());
+
+    let size = |group: &[&Movie]| group.iter().map(|m| m.size).sum::<f32>();
+    let rating = |group: &[&Movie]| group.iter().map(|m| score(m)).fold(0.0, f32::max);
+    match by_rating {
+        true => groups.sort_by(|a, b| rating(b).total_cmp(&rating(a)).then(size(b).total_cmp(&size(a)))),
+        false => groups.sort_by(|a, b| size(b).total_cmp(&size(a))),
+    }
+
+    let mut placed = Vec::new();
+    let mut unplaced = Vec::new();
+    for mut group in groups {
+        let collection = group[0].collection.clone();
+        let home = collection.as_ref().and_then(|c| homes.get(c).copied());
+
+        if let Some(drive) = pick(drives, size(&group), home) {
+            drives[drive].used += size(&group);
+            placed.extend(group.into_iter().map(|m| (m, drive)));
+            if let Some(c) = collection {
+                homes.entry(c).or_insert(drive);
+            }
+            continue;
+        }
+
+        // Too big for any one drive, so split it
+        match by_rating {
+            true => group.sort_by(|a, b| score(b).total_cmp(&score(a))),
+            false => group.sort_by(|a, b| b.size.total_cmp(&a.size)),
+        }
+        for movie in group {
+            let home = collection.as_ref().and_then(|c| homes.get(c).copied());
+            match pick(drives, movie.size, home) {
+                Some(drive) => {
+                    drives[drive].used += movie.size;
+                    placed.push((movie, drive));
+                    if let Some(c) = &collection {
+                        homes.entry(c.clone()).or_insert(drive);
+                    }
+                }
+                None => unplaced.push(movie),
+            }
+        }
+    }
+    (placed, unplaced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drives(capacities: &[f32]) -> Vec<Drive> {
+        capacities.iter().map(|capacity| Drive { capacity: *capacity, used: 0.0 }).collect()
+    }
+
+    #[test]
+    fn small_rips_fill_the_space_they_take() {
+        // Eight 700 MB rips take 5.5 GB, so they fit on a 6 GB drive but not on a 5 GB one
+        let rips = (0..8)
+            .map(|i| Movie::fixture(&format!("Rip {i}"), 2000 + i, Movie::make_gb(700 << 20), i as u32))
+            .collect::<Vec<_>>();
+
+        let mut small = drives(&[5.0]);
+        let (placed, unplaced) = place(&mut small, HashMap::new(), rips.iter().collect(), false);
+        assert_eq!((placed.len(), unplaced.len()), (7, 1));
+        assert!(small[0].free() >= 0.0);
+
+        let mut large = drives(&[6.0]);
+        let (placed, unplaced) = place(&mut large, HashMap::new(), rips.iter().collect(), false);
+        assert_eq!((placed.len(), unplaced.len()), (8, 0));
+        assert!((large[0].used - 5.47).abs() < 0.01, "{}", large[0].used);
+    }
+
+    #[test]
+    fn films_over_capacity_are_left_unplaced() {
+        let films = [
+            Movie::fixture("Big", 2001, 40.0, 1),
+            Movie::fixture("Small", 2002, 0.5, 2),
+            Movie::fixture("Huge", 2003, 120.0, 3),
+        ];
+        let mut d = drives(&[50.0, 30.0]);
+        let (placed, unplaced) = place(&mut d, HashMap::new(), films.iter().collect(), false);
+
+        let placed = placed.iter().map(|(m, drive)| (m.title.as_str(), *drive)).collect::<Vec<_>>();
+        assert_eq!(placed, [("Big", 0), ("Small", 1)]);
+        assert_eq!(unplaced.iter().map(|m| m.title.as_str()).collect::<Vec<_>>(), ["Huge"]);
+        assert!(d.iter().all(|drive| drive.used <= drive.capacity));
+    }
+
+    #[test]
+    fn a_collection_over_capacity_is_split_and_loses_its_lowest_rated() {
+        let mut films = [(30.0, 9.0), (30.0, 4.0), (30.0, 7.0)]
+            .iter()
+            .enumerate()
+            .map(|(i, (size, rating))| {
+                let mut film = Movie::fixture(&format!("Part {i}"), 2000 + i as i16, *size, i as u32);
+                film.user_rating = Some(*rating);
+                film
+            })
+            .collect::<Vec<_>>();
+        for film in &mut films {
+            film.collection = Some("Trilogy".to_string());
+        }
+        let mut d = drives(&[40.0, 35.0]);
+        let (placed, unplaced) = place(&mut d, HashMap::new(), films.iter().collect(), true);
+
+        let placed = placed.iter().map(|(m, drive)| (m.title.as_str(), *drive)).collect::<Vec<_>>();
+        assert_eq!(placed, [("Part 0", 0), ("Part 2", 1)]);
+        assert_eq!(unplaced.iter().map(|m| m.title.as_str()).collect::<Vec<_>>(), ["Part 1"]);
+        assert!(d.iter().all(|drive| drive.used <= drive.capacity));
+    }
+
+    #[test]
+    fn collections_stay_together_and_go_home() {
+        let mut films = (1..=3)
+            .map(|i| Movie::fixture(&format!("Part {i}"), 2000 + i, 10.0, i as u32))
+            .collect::<Vec<_>>();
+        for film in &mut films {
+            film.collection = Some("Trilogy".to_string());
+        }
+        let mut d = drives(&[100.0, 100.0]);
+        d[1].used = 50.0;
+        let homes = HashMap::from([("Trilogy".to_string(), 1)]);
+        let (placed, _) = place(&mut d, homes, films.iter().collect(), false);
+        assert!(placed.iter().all(|(_, drive)| *drive == 1));
+        assert_eq!(d[1].used, 80.0);
+    }
+}
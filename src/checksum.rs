@@ -0,0 +1,192 @@
+use crate::{
+    error::{self, Error},
+    movie::Movie,
+};
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+use xxhash_rust::xxh3::Xxh3;
+
+const CHUNK: usize = 1 << 20;
+
+/// xxh3-128 of a file's content, taken while its size and mtime (`file_hash`)
+/// stayed the same
+#[derive(Debug)]
+pub struct Checksum {
+    pub path: PathBuf,
+    pub file_hash: u32,
+    pub value: u128,
+}
+
+impl Checksum {
+    pub fn hex(&self) -> String {
+        format!("{:032x}", self.value)
+    }
+}
+
+/// Hash a file's content, reading at most `bytes_per_sec` (0 for no limit)
+pub fn xxh3_128(path: &Path, bytes_per_sec: u64) -> io::Result<u128> {
+    let mut file = File::open(path)?;
+    let mut hasher = Xxh3::new();
+    let mut buf = vec![0; CHUNK];
+    let mut read = 0;
+    let start = Instant::now();
+
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        read += n as u64;
+
+        if bytes_per_sec > 0 {
+            let due = Duration::from_secs_f64(read as f64 / bytes_per_sec as f64);
+            if let Some(ahead) = due.checked_sub(start.elapsed()) {
+                thread::sleep(ahead);
+            }
+        }
+    }
+    Ok(hasher.digest128())
+}
+
+/// Hash a file, failing if it changes while being read (a copy still in progress)
+pub fn compute(path: &Path, bytes_per_sec: u64) -> error::Result<Checksum> {
+    let file_hash = Movie::read_metadata(path)?.1;
+    let value = xxh3_128(path, bytes_per_sec).map_err(Error::io(path))?;
+    if Movie::read_metadata(path)?.1 != file_hash {
+        return Err(Error::io(path)(io::Error::other("file changed while it was hashed")));
+    }
+    Ok(Checksum {
+        path: path.to_path_buf(),
+        file_hash,
+        value,
+    })
+}
+
+/// Hashes queued files one at a time on a background thread, so a scan
+/// does not wait on reading whole files
+#[derive(Debug)]
+pub struct Hasher {
+    paths: Sender<PathBuf>,
+    results: Receiver<(PathBuf, error::Result<Checksum>)>,
+    /// Files whose result has not been taken yet
+    queued: HashSet<PathBuf>,
+}
+
+impl Hasher {
+    pub fn start(bytes_per_sec: u64) -> Self {
+        let (paths, queue) = mpsc::channel::<PathBuf>();
+        let (done, results) = mpsc::channel();
+        thread::spawn(move || {
+            for path in queue {
+                let checksum = compute(&path, bytes_per_sec);
+                if done.send((path, checksum)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Hasher {
+            paths,
+            results,
+            queued: HashSet::new(),
+        }
+    }
+
+    /// Queue a file, unless it is already waiting
+    pub fn queue(&mut self, path: PathBuf) {
+        if !self.queued.contains(&path) && self.paths.send(path.clone()).is_ok() {
+            self.queued.insert(path);
+        }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.queued.len()
+    }
+
+    /// Results that are ready, waiting up to `timeout` for the first one
+    pub fn results(&mut self, timeout: Duration) -> Vec<(PathBuf, error::Result<Checksum>)> {
+        let mut results = Vec::new();
+        if self.queued.is_empty() {
+            return results;
+        }
+        match self.results.recv_timeout(timeout) {
+            Ok(result) => results.push(result),
+            Err(RecvTimeoutError::Timeout) => {}
+            // The worker is gone, so nothing else will come
+            Err(RecvTimeoutError::Disconnected) => self.queued.clear(),
+        }
+        results.extend(self.results.try_iter());
+        for (path, _) in &results {
+            self.queued.remove(path);
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_file(name: &str, content: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("numov-checksum-{name}-{}", std::process::id()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn a_rewrite_that_keeps_size_and_mtime_changes_only_the_content_hash() {
+        let path = temp_file("rewrite", b"the original frames");
+        let before = compute(&path, 0).unwrap();
+        let mtime = fs::metadata(&path).unwrap().modified().unwrap();
+
+        fs::write(&path, b"the damaged  frames").unwrap();
+        File::options().write(true).open(&path).unwrap().set_modified(mtime).unwrap();
+        let after = compute(&path, 0).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(before.file_hash, after.file_hash);
+        assert_ne!(before.hex(), after.hex());
+        assert_eq!(before.hex().len(), 32);
+    }
+
+    #[test]
+    fn the_same_content_hashes_the_same_at_any_rate() {
+        let path = temp_file("rate", &[7; 3 * CHUNK / 2]);
+        let unlimited = xxh3_128(&path, 0).unwrap();
+        let limited = xxh3_128(&path, 64 * CHUNK as u64).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(unlimited, limited);
+    }
+
+    #[test]
+    fn queued_files_are_hashed_once_in_the_background() {
+        let path = temp_file("queue", b"frames");
+        let mut hasher = Hasher::start(0);
+        hasher.queue(path.clone());
+        hasher.queue(path.clone());
+        assert_eq!(hasher.pending(), 1);
+
+        let results = hasher.results(Duration::from_secs(10));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.as_ref().unwrap().value, xxhash_rust::xxh3::xxh3_128(b"frames"));
+        assert_eq!(hasher.pending(), 0);
+    }
+
+    #[test]
+    fn a_missing_file_has_no_checksum() {
+        let path = std::env::temp_dir().join(format!("numov-checksum-missing-{}", std::process::id()));
+        assert!(matches!(compute(&path, 0), Err(Error::Io { .. })));
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{movie::Movie, work::Work};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt};
+
+/// One film's files under one of the compared roots
+pub struct FilmCopy<'a> {
+    pub movie: &'a Movie,
+    /// Movie folder, relative to its root
+    pub path: String,
+    /// Exact size of all parts
+    pub bytes: u64,
+    /// Content checksums of the parts, if all of them are stored and current
+    pub checksum: Option<String>,
+}
+
+impl FilmCopy<'_> {
+    /// Same checksum if both sides have one, else the same size to the byte
+    /// and the same duration and video stream
+    fn same_content(&self, other: &FilmCopy) -> bool {
+        match (&self.checksum, &other.checksum) {
+            (Some(a), Some(b)) => a == b,
+            _ => {
+                self.bytes == other.bytes
+                    && self.movie.duration == other.movie.duration
+                    && self.side().video == other.side().video
+            }
+        }
+    }
+
+    fn side(&self) -> Side {
+        let m = self.movie;
+        Side {
+            path: self.path.clone(),
+            size: m.size,
+            video: format!("{} {} {} {}", m.video.resolution, m.video.codec, m.video.bit_depth, m.video.hdr),
+        }
+    }
+}
+
+/// How a film differs between the two roots
+#[derive(Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// Only under root A
+    MissingFromB,
+    /// Only under root B
+    MissingFromA,
+    /// B's best version is worse than A's
+    LowerQualityInB,
+    /// A's best version is worse than B's
+    LowerQualityInA,
+    /// Same quality, but not the same file
+    Differs,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Status::MissingFromB => "Missing from B",
+            Status::MissingFromA => "Missing from A",
+            Status::LowerQualityInB => "Lower quality in B",
+            Status::LowerQualityInA => "Lower quality in A",
+            Status::Differs => "Different file",
+        };
+        write!(f, "{text}")
+    }
+}
+
+/// A film's copy under one root, as shown in a `Difference`
+#[derive(Debug, Serialize)]
+pub struct Side {
+    /// Movie folder, relative to its root
+    pub path: String,
+    /// GB
+    pub size: f32,
+    /// Resolution, codec, bit depth and HDR
+    pub video: String,
+}
+
+/// A film that is not the same under both roots
+#[derive(Debug, Serialize)]
+pub struct Difference {
+    /// How it differs
+    pub status: Status,
+    /// Display name of the film
+    pub movie: String,
+    /// Its copy under root A
+    pub a: Option<Side>,
+    /// Its copy under root B
+    pub b: Option<Side>,
+}
+
+/// Match the films under two roots, first by content and then by title, year and edition.
+/// Returns how many films are identical on both sides, and how the rest differ.
+pub fn diff(a: Vec<FilmCopy>, b: Vec<FilmCopy>) -> (usize, Vec<Difference>) {
+    let mut b = b.into_iter().map(Some).collect::<Vec<_>>();
+    let mut identical = 0;
+    let mut left: BTreeMap<String, (Vec<FilmCopy>, Vec<FilmCopy>)> = BTreeMap::new();
+
+    for copy in a {
+        match b.iter_mut().find(|other| other.as_ref().is_some_and(|o| copy.same_content(o))) {
+            Some(other) => {
+                *other = None;
+                identical += 1;
+            }
+            None => left.entry(Work::key(copy.movie)).or_default().0.push(copy),
+        }
+    }
+    for copy in b.into_iter().flatten() {
+        left.entry(Work::key(copy.movie)).or_default().1.push(copy);
+    }
+
+    let best = |copies: &[FilmCopy]| copies.iter().max_by_key(|c| c.movie.quality_score()).map(FilmCopy::side);
+    let mut differences = Vec::new();
+    for (as_, bs) in left.values() {
+        let (a_best, b_best) = (best(as_), best(bs));
+        let score = |copies: &[FilmCopy]| copies.iter().map(|c| c.movie.quality_score()).max();
+        let status = match (score(as_), score(bs)) {
+            (Some(_), None) => Status::MissingFromB,
+            (None, Some(_)) => Status::MissingFromA,
+            (Some(x), Some(y)) if x > y => Status::LowerQualityInB,
+            (Some(x), Some(y)) if x < y => Status::LowerQualityInA,
+            _ => Status::Differs,
+        };
+        let Some(first) = as_.first().or(bs.first()) else {
+            continue;
+        };
+        let movie = first.movie.display_name();
+        differences.push(Difference {
+            status,
+            movie,
+            a: a_best,
+            b: b_best,
+        });
+    }
+
+    differences.sort_by(|x, y| (&x.status, &x.movie).cmp(&(&y.status, &y.movie)));
+    (identical, differences)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::movie_types::resolution::Resolution;
+
+    fn copy<'a>(movie: &'a Movie, checksum: &str) -> FilmCopy<'a> {
+        FilmCopy {
+            movie,
+            path: movie.display_name(),
+            bytes: 1,
+            checksum: Some(checksum.to_string()),
+        }
+    }
+
+    #[test]
+    fn same_content_matches_whatever_the_name() {
+        let a = Movie::fixture("Heat", 1995, 8.0, 1);
+        let b = Movie::fixture("Heat (Extended)", 1995, 8.0, 2);
+
+        let (identical, differences) = diff(vec![copy(&a, "aa")], vec![copy(&b, "aa")]);
+        assert_eq!(identical, 1);
+        assert!(differences.is_empty());
+    }
+
+    #[test]
+    fn other_content_of_a_work_is_ranked_by_quality() {
+        let a = Movie::fixture("Heat", 1995, 8.0, 1);
+        let mut b = Movie::fixture("Heat", 1995, 40.0, 2);
+        b.video.resolution = Resolution::UHD4K;
+        let c = Movie::fixture("Ran", 1985, 8.0, 3);
+
+        let (identical, differences) = diff(vec![copy(&a, "aa"), copy(&c, "cc")], vec![copy(&b, "bb")]);
+        assert_eq!(identical, 0);
+        let found = differences.iter().map(|d| (d.movie.as_str(), &d.status)).collect::<Vec<_>>();
+        assert_eq!(found, [("Ran (1985)", &Status::MissingFromB), ("Heat (1995)", &Status::LowerQualityInA)]);
+    }
+
+    #[test]
+    fn editions_are_not_versions_of_each_other() {
+        let mut cut = Movie::fixture("Blade Runner", 1982, 8.0, 1);
+        cut.edition = Some("Final Cut".to_string());
+        let theatrical = Movie::fixture("Blade Runner", 1982, 8.0, 2);
+
+        let (_, differences) = diff(vec![copy(&cut, "aa")], vec![copy(&theatrical, "bb")]);
+        let found = differences.iter().map(|d| (d.movie.as_str(), &d.status)).collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                ("Blade Runner (1982) {Final Cut}", &Status::MissingFromB),
+                ("Blade Runner (1982)", &Status::MissingFromA),
+            ]
+        );
+    }
+}
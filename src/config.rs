@@ -0,0 +1,349 @@
+use crate::database::Database;
+use crate::error::{Error, Result};
+use crate::pattern::NamePattern;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env,
+    path::{Path, PathBuf},
+};
+use toml::{Table, Value};
+
+/// User configuration read from `numov/config.toml` in the config directory
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Library root for commands that are not given a path
+    pub root: Option<PathBuf>,
+    /// Database file, relative to the numov config directory unless absolute
+    pub db: Option<PathBuf>,
+    /// Title extraction patterns, tried in order before the defaults
+    pub(crate) patterns: Vec<NamePattern>,
+    /// Which files count as extras
+    pub extras: ExtrasConfig,
+    /// How the library root is walked and files are read
+    pub scan: ScanConfig,
+    /// How `rename` names movie folders
+    pub rename: RenameConfig,
+    /// How `scan --watch` reacts to changes
+    pub watch: WatchConfig,
+    /// Background content checksums
+    pub checksum: ChecksumConfig,
+    /// How tables are printed
+    pub tables: TablesConfig,
+    /// How letterboxd ratings are matched to movies
+    pub ratings: RatingsConfig,
+    /// Named sets of settings, such as `movies` and `anime`, that override the
+    /// ones above when picked with `--profile`
+    pub profiles: BTreeMap<String, Table>,
+}
+
+/// `[extras]` settings
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ExtrasConfig {
+    /// Files shorter than this, next to a longer main feature, are treated as extras
+    pub max_minutes: u64,
+}
+
+impl Default for ExtrasConfig {
+    fn default() -> Self {
+        ExtrasConfig { max_minutes: 20 }
+    }
+}
+
+/// `[scan]` settings
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ScanConfig {
+    /// How deep below the root movie files are looked for. Folders between
+    /// the root and a movie's folder are its collection.
+    pub max_depth: usize,
+    /// gitignore-style globs of paths to skip, relative to the root
+    pub exclude: Vec<String>,
+    /// New movies and extras are saved every this many, so an interrupted scan can resume
+    pub batch_size: usize,
+    /// Most a file's headers may take up, in KB. Tags and chapters past it are
+    /// skipped; a file whose Info and Tracks do not fit is not read at all.
+    pub read_budget_kb: u64,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        ScanConfig {
+            max_depth: 2,
+            exclude: Vec::new(),
+            batch_size: 25,
+            read_budget_kb: 1024,
+        }
+    }
+}
+
+impl ScanConfig {
+    /// `read_budget_kb` in bytes
+    pub fn read_budget(&self) -> u64 {
+        self.read_budget_kb * 1024
+    }
+}
+
+/// `[rename]` settings
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct RenameConfig {
+    /// Folder name template, see `Library::get_new_name` for the tokens
+    pub format: String,
+}
+
+impl Default for RenameConfig {
+    fn default() -> Self {
+        RenameConfig {
+            format: "{title} ({year}){edition} [{res} {codec} {bits} {audio}-{channels}] ({size} GB)".to_string(),
+        }
+    }
+}
+
+/// `[watch]` settings
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// A changed folder is rescanned once nothing in it has changed for this long,
+    /// so files that are still being copied are not read half-written
+    pub debounce_secs: u64,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        WatchConfig { debounce_secs: 10 }
+    }
+}
+
+/// `[checksum]` settings
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ChecksumConfig {
+    /// Hash the full content of new and changed files in the background after a scan
+    pub enabled: bool,
+    /// Read limit for background hashing, so playback from the same disk is not starved.
+    /// 0 reads as fast as the disk allows.
+    pub max_mb_per_sec: u64,
+}
+
+impl Default for ChecksumConfig {
+    fn default() -> Self {
+        ChecksumConfig {
+            enabled: false,
+            max_mb_per_sec: 50,
+        }
+    }
+}
+
+/// `[tables]` settings, applied to every table numov prints
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct TablesConfig {
+    /// polars table style, such as `UTF8_BORDERS_ONLY`, `UTF8_FULL` or `ASCII_MARKDOWN`
+    pub style: String,
+    /// Round the corners of table borders
+    pub rounded_corners: bool,
+    /// Print the shape of a table above it
+    pub show_shape: bool,
+    /// Print the type of each column under its name
+    pub show_types: bool,
+    /// Rows printed per table, -1 for all
+    pub max_rows: i64,
+    /// Longest text printed per cell
+    pub max_str_len: usize,
+    /// Rows of the `stats` tables that are cut short (all but `full` and `seasons`)
+    pub stats_rows: usize,
+    /// Rows printed by `query`, `lint`, `verify`, `fsck`, `compare`, `plan-backup`,
+    /// `bench-probe` and `stats full`, -1 for all
+    pub report_rows: i64,
+    /// Longest text printed per cell of those, so problems are not cut short
+    pub report_str_len: usize,
+}
+
+impl Default for TablesConfig {
+    fn default() -> Self {
+        TablesConfig {
+            style: "UTF8_BORDERS_ONLY".to_string(),
+            rounded_corners: true,
+            show_shape: false,
+            show_types: false,
+            max_rows: 25,
+            max_str_len: 35,
+            stats_rows: 20,
+            report_rows: -1,
+            report_str_len: 60,
+        }
+    }
+}
+
+impl TablesConfig {
+    /// Set polars' table formatting to these settings
+    pub fn apply(&self) {
+        self.apply_with(self.max_rows, self.max_str_len);
+    }
+
+    /// Set polars' table formatting for reports, which are printed in full by default
+    pub fn apply_report(&self) {
+        self.apply_with(self.report_rows, self.report_str_len);
+    }
+
+    fn apply_with(&self, max_rows: i64, max_str_len: usize) {
+        let flag = |on: bool| if on { "1" } else { "0" };
+        env::set_var("POLARS_FMT_TABLE_FORMATTING", &self.style);
+        env::set_var("POLARS_FMT_TABLE_HIDE_DATAFRAME_SHAPE_INFORMATION", flag(!self.show_shape));
+        env::set_var("POLARS_FMT_TABLE_ROUNDED_CORNERS", flag(self.rounded_corners));
+        env::set_var("POLARS_FMT_TABLE_HIDE_COLUMN_DATA_TYPES", flag(!self.show_types));
+        env::set_var("POLARS_FMT_MAX_ROWS", max_rows.to_string());
+        env::set_var("POLARS_FMT_STR_LEN", max_str_len.to_string());
+    }
+}
+
+/// `[ratings]` settings
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct RatingsConfig {
+    /// How alike (Jaro-Winkler, 0 to 1) a letterboxd title must be to a movie's title to take its rating
+    pub match_threshold: f64,
+}
+
+impl Default for RatingsConfig {
+    fn default() -> Self {
+        RatingsConfig { match_threshold: 0.9 }
+    }
+}
+
+impl Config {
+    /// The numov folder in the user's config directory
+    pub fn dir() -> Result<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("numov")).ok_or(Error::NoConfigDir)
+    }
+
+    /// Where the config file is read from
+    pub fn path() -> Result<PathBuf> {
+        Ok(Self::dir()?.join("config.toml"))
+    }
+
+    /// Read the config file, or use defaults if there is none
+    pub fn load() -> Result<Self> {
+        Self::load_profile(None)
+    }
+
+    /// Read the config file with the settings of `profile` laid over it
+    pub fn load_profile(profile: Option<&str>) -> Result<Self> {
+        let path = Self::path()?;
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(Error::io(&path)(e)),
+        };
+        Self::parse(&contents, profile, &path)
+    }
+
+    /// Read the settings in `contents`, the text of the config file at `path`
+    fn parse(contents: &str, profile: Option<&str>, path: &Path) -> Result<Self> {
+        let invalid = |reason: String| Error::Config {
+            path: path.to_path_buf(),
+            reason,
+        };
+
+        let mut table = toml::from_str::<Table>(contents).map_err(|e| invalid(e.to_string()))?;
+        if let Some(name) = profile {
+            let settings = table
+                .get("profiles")
+                .and_then(|profiles| profiles.get(name))
+                .and_then(Value::as_table)
+                .cloned()
+                .ok_or_else(|| invalid(format!("There is no profile named {name:?}")))?;
+            merge(&mut table, settings);
+        }
+        table.try_into().map_err(|e| invalid(e.to_string()))
+    }
+
+    /// The database file, `data.db` in the config directory unless `db` is set
+    pub fn db_path(&self) -> Result<PathBuf> {
+        match &self.db {
+            Some(db) => Ok(Self::dir()?.join(db)),
+            None => Database::default_path(),
+        }
+    }
+}
+
+/// Lay `over` onto `base`. Tables are merged key by key; anything else, arrays
+/// included, replaces what was there.
+fn merge(base: &mut Table, over: Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(over)) => merge(base, over),
+            (_, value) => _ = base.insert(key, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        root = "/films"
+
+        [scan]
+        max_depth = 3
+        exclude = ["Samples/", "*.part"]
+
+        [[patterns]]
+        name = "scene"
+        target = "file"
+        regex = '(?P<title>.+)\.(?P<year>\d{4})\.'
+
+        [profiles.anime]
+        root = "/anime"
+        scan = { exclude = ["Specials/"] }
+        tables = { max_rows = 10 }
+
+        [profiles.plain]
+        patterns = []
+    "#;
+
+    fn load(profile: Option<&str>) -> Result<Config> {
+        Config::parse(CONFIG, profile, Path::new("config.toml"))
+    }
+
+    #[test]
+    fn a_profile_merges_tables_key_by_key() {
+        let config = load(Some("anime")).unwrap();
+        assert_eq!(config.root.as_deref(), Some(Path::new("/anime")));
+        assert_eq!(config.scan.max_depth, 3);
+        assert_eq!(config.tables.max_rows, 10);
+        assert_eq!(config.tables.max_str_len, TablesConfig::default().max_str_len);
+    }
+
+    #[test]
+    fn a_profile_replaces_lists_whole() {
+        let anime = load(Some("anime")).unwrap();
+        assert_eq!(anime.scan.exclude, ["Specials/"]);
+        assert_eq!(anime.patterns.len(), 1);
+
+        let plain = load(Some("plain")).unwrap();
+        assert!(plain.patterns.is_empty());
+        assert_eq!(plain.scan.exclude, ["Samples/", "*.part"]);
+    }
+
+    #[test]
+    fn without_a_profile_the_top_level_settings_apply() {
+        let config = load(None).unwrap();
+        assert_eq!(config.root.as_deref(), Some(Path::new("/films")));
+        assert_eq!(config.patterns[0].name, "scene");
+        assert_eq!(config.profiles.keys().collect::<Vec<_>>(), ["anime", "plain"]);
+    }
+
+    #[test]
+    fn an_unknown_profile_names_the_config_file() {
+        let err = load(Some("music")).unwrap_err();
+        assert!(
+            matches!(&err, Error::Config { path, reason } if path == Path::new("config.toml") && reason.contains("music")),
+            "{err:?}"
+        );
+    }
+}
@@ -1,24 +1,48 @@
-use crate::movie::{AudioStream, Movie, SubtitleStream, VideoStream};
+use crate::{
+    checksum::Checksum,
+    config::Config,
+    error::Error,
+    extras::Extra,
+    movie::{AudioStream, AudioTrack, Movie, SubtitleStream, SubtitleTrack, VideoStream},
+    tv::Episode,
+    verify::Verification,
+    work::Work,
+};
 use rusqlite::{params, Connection, Result};
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
 
+/// The SQLite store of a library: movies, extras, episodes, ratings and the
+/// results of checks. Tables are created on open.
 #[derive(Debug)]
 pub struct Database {
-    pub conn: Connection,
+    pub(crate) conn: Connection,
 }
 
 impl Database {
-    pub fn open() -> Result<Self> {
-        
-        let db_path = dirs::config_dir().unwrap().join("numov");
-        if std::fs::metadata(&db_path).is_err() {
-            std::fs::create_dir(&db_path).unwrap();
-        }
-        //
-        let conn = Connection::open(db_path.join("data.db"))?;
-        // let conn = Connection::open(db_path.join("data.db")).unwrap_or_else(|e| println!("{}", e));
-        // let conn = Connection::open("numov.db")?;
-        // let x = PathBuf::from(conn.path().unwrap());
+    /// `data.db` in the numov config dir
+    pub fn default_path() -> crate::error::Result<PathBuf> {
+        Ok(Config::dir()?.join("data.db"))
+    }
+
+    /// Open the database at the default path
+    pub fn open() -> crate::error::Result<Self> {
+        Self::open_at(&Self::default_path()?)
+    }
+
+    /// Close the connection, reporting anything SQLite could not flush
+    pub fn close(self) -> crate::error::Result<()> {
+        self.conn.close().map_err(|(_, e)| e.into())
+    }
+
+    /// Open or create the database at `db_path`, creating its folder if needed
+    pub fn open_at(db_path: &Path) -> crate::error::Result<Self> {
+        if let Some(dir) = db_path.parent() {
+            std::fs::create_dir_all(dir).map_err(Error::io(dir))?;
+        }
+        let conn = Connection::open(db_path)?;
 
         conn.execute(
             "CREATE TABLE IF NOT EXISTS movies(
@@ -35,11 +59,76 @@ impl Database {
                         aud_count INTEGER NOT NULL,
                         sub_format TEXT NOT NULL,
                         sub_count INTEGER NOT NULL,
+                        source TEXT,
+                        release_group TEXT,
+                        edition TEXT,
+                        imdb_id TEXT,
+                        name_pattern TEXT,
+                        original_title TEXT,
+                        tmdb_id TEXT,
+                        runtime INTEGER,
+                        user_rating REAL,
+                        hdr TEXT,
+                        collection TEXT,
                         hash INTEGER NOT NULL PRIMARY KEY 
                     )",
             [],
         )?;
 
+        // Columns added after the initial schema
+        add_column(&conn, "movies", "source", "TEXT")?;
+        add_column(&conn, "movies", "release_group", "TEXT")?;
+        add_column(&conn, "movies", "edition", "TEXT")?;
+        add_column(&conn, "movies", "imdb_id", "TEXT")?;
+        add_column(&conn, "movies", "name_pattern", "TEXT")?;
+        add_column(&conn, "movies", "original_title", "TEXT")?;
+        add_column(&conn, "movies", "tmdb_id", "TEXT")?;
+        add_column(&conn, "movies", "runtime", "INTEGER")?;
+        add_column(&conn, "movies", "user_rating", "REAL")?;
+        add_column(&conn, "movies", "hdr", "TEXT")?;
+        add_column(&conn, "movies", "collection", "TEXT")?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS genres(
+                        hash INTEGER NOT NULL,
+                        genre TEXT NOT NULL,
+                        PRIMARY KEY (hash, genre)
+                    )", [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS audio_tracks(
+                        hash INTEGER NOT NULL,
+                        idx INTEGER NOT NULL,
+                        codec TEXT NOT NULL,
+                        channels NUMERIC NOT NULL,
+                        language TEXT NOT NULL,
+                        PRIMARY KEY (hash, idx)
+                    )", [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS subtitle_tracks(
+                        hash INTEGER NOT NULL,
+                        idx INTEGER NOT NULL,
+                        format TEXT NOT NULL,
+                        language TEXT NOT NULL,
+                        forced INTEGER NOT NULL,
+                        file TEXT,
+                        PRIMARY KEY (hash, idx)
+                    )", [],
+        )?;
+        add_column(&conn, "subtitle_tracks", "file", "TEXT")?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS parts(
+                        hash INTEGER NOT NULL,
+                        idx INTEGER NOT NULL,
+                        file TEXT NOT NULL,
+                        PRIMARY KEY (hash, idx)
+                    )", [],
+        )?;
+
         conn.execute(
             "CREATE TABLE IF NOT EXISTS ratings(
                         title TEXT PRIMARY KEY,
@@ -47,18 +136,189 @@ impl Database {
                     )", [],
         )?;
 
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS extras(
+                        hash INTEGER NOT NULL PRIMARY KEY,
+                        movie_hash INTEGER,
+                        kind TEXT NOT NULL,
+                        name TEXT NOT NULL,
+                        size REAL NOT NULL,
+                        duration TEXT NOT NULL
+                    )", [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS verifications(
+                        hash INTEGER NOT NULL PRIMARY KEY,
+                        ok INTEGER NOT NULL,
+                        problems TEXT NOT NULL,
+                        verified_at INTEGER NOT NULL
+                    )", [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS checksums(
+                        path TEXT NOT NULL PRIMARY KEY,
+                        file_hash INTEGER NOT NULL,
+                        xxh3 TEXT NOT NULL,
+                        checked_at INTEGER NOT NULL
+                    )", [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS backup_drives(
+                        idx INTEGER NOT NULL PRIMARY KEY,
+                        capacity REAL NOT NULL
+                    )", [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS backup_works(
+                        work TEXT NOT NULL PRIMARY KEY,
+                        drive INTEGER NOT NULL
+                    )", [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS shows(
+                        title TEXT NOT NULL PRIMARY KEY,
+                        year INTEGER
+                    )", [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS seasons(
+                        show TEXT NOT NULL,
+                        season INTEGER NOT NULL,
+                        PRIMARY KEY (show, season)
+                    )", [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS episodes(
+                        show TEXT NOT NULL,
+                        season INTEGER NOT NULL,
+                        episode INTEGER NOT NULL,
+                        last_episode INTEGER NOT NULL,
+                        title TEXT,
+                        size REAL NOT NULL,
+                        duration TEXT NOT NULL,
+                        resolution TEXT NOT NULL,
+                        vid_codec TEXT NOT NULL,
+                        bit_depth TEXT NOT NULL,
+                        hdr TEXT NOT NULL,
+                        aud_codec TEXT NOT NULL,
+                        channels NUMERIC NOT NULL,
+                        aud_count INTEGER NOT NULL,
+                        sub_format TEXT NOT NULL,
+                        sub_count INTEGER NOT NULL,
+                        hash INTEGER NOT NULL PRIMARY KEY
+                    )", [],
+        )?;
+
+        migrate(&conn)?;
         Ok(Database { conn })
     }
 
-    pub fn update_movie_table(&mut self, additions: &HashMap<u32, Movie>, removals: &HashSet<u32>) -> rusqlite::Result<()> {
+    pub(crate) fn update_extras_table(&mut self, additions: &[&Extra], removals: &HashSet<u32>) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO extras (hash, movie_hash, kind, name, size, duration) VALUES (?, ?, ?, ?, ?, ?)",
+            )?;
+            for extra in additions {
+                stmt.execute(params![&extra.hash,
+                        &extra.movie_hash,
+                        &extra.kind,
+                        &extra.name,
+                        format!("{:.2}", &extra.size),
+                        &extra.duration]
+                )?;
+            }
+
+            let mut stmt = tx.prepare("DELETE FROM extras WHERE hash = (?)")?;
+            for hash in removals {
+                stmt.execute(params![hash])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Write new episodes and remove deleted ones, keeping the shows and seasons tables in step
+    pub(crate) fn update_episode_table(&mut self, additions: &HashMap<u32, Episode>, removals: &HashSet<u32>) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO episodes (Show, Season, Episode, Last_episode, Title, Size, Duration, Resolution, Vid_codec, Bit_depth, Hdr, Aud_codec, Channels, Aud_count, Sub_format, Sub_count, Hash) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )?;
+            let mut show_stmt = tx.prepare("INSERT OR REPLACE INTO shows (title, year) VALUES (?, ?)")?;
+            let mut season_stmt = tx.prepare("INSERT OR IGNORE INTO seasons (show, season) VALUES (?, ?)")?;
+
+            for ep in additions.values() {
+                stmt.execute(params![&ep.show,
+                        &ep.season,
+                        &ep.episode,
+                        &ep.last_episode,
+                        &ep.title,
+                        format!("{:.2}", &ep.size),
+                        &ep.duration,
+                        &ep.video.resolution,
+                        &ep.video.codec,
+                        &ep.video.bit_depth,
+                        &ep.video.hdr,
+                        &ep.audio.codec,
+                        &ep.audio.channels.to_string(),
+                        &ep.audio.count,
+                        &ep.subs.format,
+                        &ep.subs.count,
+                        &ep.hash]
+                )?;
+                show_stmt.execute(params![&ep.show, &ep.show_year])?;
+                season_stmt.execute(params![&ep.show, &ep.season])?;
+            }
+        }
+        {
+            let mut stmt = tx.prepare("DELETE FROM episodes WHERE hash = (?)")?;
+            for hash in removals {
+                stmt.execute(params![hash])?;
+            }
+        }
+        tx.execute(
+            "DELETE FROM seasons WHERE NOT EXISTS
+                (SELECT 1 FROM episodes e WHERE e.show = seasons.show AND e.season = seasons.season)",
+            [],
+        )?;
+        tx.execute(
+            "DELETE FROM shows WHERE NOT EXISTS (SELECT 1 FROM episodes e WHERE e.show = shows.title)",
+            [],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Save `additions` with their tracks, parts and genres, and delete `removals`
+    pub(crate) fn update_movie_table<'a>(
+        &mut self,
+        additions: impl IntoIterator<Item = &'a Movie>,
+        removals: &HashSet<u32>,
+    ) -> rusqlite::Result<()> {
         let tx = self.conn.transaction()?;
         {
 
             let mut stmt = tx.prepare( 
-                "INSERT OR REPLACE INTO movies (Title, Year, Rating, Size, Duration, Resolution, Vid_codec, Bit_depth, Aud_codec, Channels, Aud_count, Sub_format, Sub_count, Hash) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                "INSERT OR REPLACE INTO movies (Title, Year, Rating, Size, Duration, Resolution, Vid_codec, Bit_depth, Aud_codec, Channels, Aud_count, Sub_format, Sub_count, Source, Release_group, Edition, Imdb_id, Name_pattern, Original_title, Tmdb_id, Runtime, User_rating, Hdr, Collection, Hash) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )?;
+            let mut genre_stmt = tx.prepare("INSERT OR IGNORE INTO genres (hash, genre) VALUES (?, ?)")?;
+            let mut audio_stmt = tx.prepare(
+                "INSERT INTO audio_tracks (hash, idx, codec, channels, language) VALUES (?, ?, ?, ?, ?)"
             )?;
+            let mut sub_stmt = tx.prepare(
+                "INSERT INTO subtitle_tracks (hash, idx, format, language, forced, file) VALUES (?, ?, ?, ?, ?, ?)"
+            )?;
+            let mut part_stmt = tx.prepare("INSERT INTO parts (hash, idx, file) VALUES (?, ?, ?)")?;
 
-            for movie in additions.values() {
+            for movie in additions {
                 stmt.execute( params![&movie.title,
                         &movie.year,
                         &movie.rating,
@@ -72,14 +332,110 @@ impl Database {
                         &movie.audio.count,
                         &movie.subs.format,
                         &movie.subs.count,
+                        &movie.source,
+                        &movie.group,
+                        &movie.edition,
+                        &movie.imdb,
+                        &movie.pattern,
+                        &movie.original_title,
+                        &movie.tmdb,
+                        &movie.runtime,
+                        &movie.user_rating,
+                        &movie.video.hdr,
+                        &movie.collection,
                         &movie.hash]
                 )?;
+
+                Self::delete_children(&tx, movie.hash)?;
+                for genre in &movie.genres {
+                    genre_stmt.execute(params![&movie.hash, genre])?;
+                }
+                for (idx, track) in movie.audio.tracks.iter().enumerate() {
+                    audio_stmt.execute(params![&movie.hash, idx, &track.codec, track.channels.to_string(), &track.language])?;
+                }
+                for (idx, track) in movie.subs.tracks.iter().enumerate() {
+                    sub_stmt.execute(params![&movie.hash, idx, &track.format, &track.language, track.forced, &track.file])?;
+                }
+                for (idx, file) in movie.parts.iter().enumerate() {
+                    part_stmt.execute(params![&movie.hash, idx, file])?;
+                }
             }
         }
         {
             let mut stmt = tx.prepare("DELETE FROM movies WHERE hash = (?)")?;
+            let mut verify_stmt = tx.prepare("DELETE FROM verifications WHERE hash = (?)")?;
             for hash in removals {
                 stmt.execute(params![hash])?;
+                verify_stmt.execute(params![hash])?;
+                Self::delete_children(&tx, *hash)?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Remove rows in the tables hanging off of a movie
+    fn delete_children(conn: &Connection, hash: u32) -> Result<()> {
+        for table in ["genres", "audio_tracks", "subtitle_tracks", "parts"] {
+            conn.execute(&format!("DELETE FROM {table} WHERE hash = (?)"), params![hash])?;
+        }
+        Ok(())
+    }
+
+    /// Store the outcome of `verify`, replacing earlier results for the same movies
+    pub(crate) fn update_verifications_table(&mut self, results: &[Verification]) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO verifications (hash, ok, problems, verified_at) VALUES (?, ?, ?, ?)",
+            )?;
+            for v in results {
+                stmt.execute(params![v.hash, v.ok(), v.problems.join("; "), v.verified_at])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store content checksums, or mark them as checked again when they are unchanged
+    pub(crate) fn update_checksums_table(&mut self, checksums: &[Checksum]) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO checksums (path, file_hash, xxh3, checked_at) VALUES (?, ?, ?, strftime('%s', 'now'))",
+            )?;
+            for c in checksums {
+                stmt.execute(params![c.path.to_string_lossy(), c.file_hash, c.hex()])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store the drive capacities of the backup plan, add newly placed works
+    /// and drop those that are gone or whose drive no longer exists
+    pub(crate) fn update_backup_plan(
+        &mut self,
+        capacities: &[f32],
+        additions: &[(String, usize)],
+        removals: &HashSet<String>,
+    ) -> rusqlite::Result<()> {
+        let tx = self.conn.transaction()?;
+        {
+            tx.execute("DELETE FROM backup_drives", [])?;
+            let mut stmt = tx.prepare("INSERT INTO backup_drives (idx, capacity) VALUES (?, ?)")?;
+            for (idx, capacity) in capacities.iter().enumerate() {
+                stmt.execute(params![idx, capacity])?;
+            }
+
+            let mut stmt = tx.prepare("INSERT OR REPLACE INTO backup_works (work, drive) VALUES (?, ?)")?;
+            for (work, drive) in additions {
+                stmt.execute(params![work, drive])?;
+            }
+
+            let mut stmt = tx.prepare("DELETE FROM backup_works WHERE work = (?)")?;
+            for work in removals {
+                stmt.execute(params![work])?;
             }
         }
         tx.commit()?;
@@ -87,7 +443,7 @@ impl Database {
     }
 
     /// Provided a Hashmap of ratings, update the 'ratings' table
-    pub fn update_ratings_table(&mut self, ratings_table: &HashMap<String, String>) -> rusqlite::Result<()> {
+    pub(crate) fn update_ratings_table(&mut self, ratings_table: &HashMap<String, String>) -> rusqlite::Result<()> {
         let tx = self.conn.transaction()?;
         {
             let mut stmt = tx.prepare(
@@ -107,19 +463,12 @@ impl Database {
 //   Fetch Data -> Fetch directly from database
 // ===============
 impl Database {
-    pub fn fetch(&self) -> (HashMap<u32, Movie>, HashMap<String, String>) {
-        let movies = self.fetch_movies().unwrap_or_else(|e| {
-            println!("Could not fetch movies. Error: {e}"); 
-            HashMap::new()
-        });
-
-        let ratings = self.fetch_ratings().unwrap_or_else(|e| {
-            println!("Failed to fetch ratings. Error: {e}");
-            HashMap::new()
-        });
-        (movies, ratings)
+    /// Every stored movie, and the imported letterboxd ratings by title
+    pub fn fetch(&self) -> Result<(HashMap<u32, Movie>, HashMap<String, String>)> {
+        Ok((self.fetch_movies()?, self.fetch_ratings()?))
     }
 
+    /// Imported letterboxd ratings by title
     pub fn fetch_ratings(&self) -> Result<HashMap<String, String>, rusqlite::Error> {
         let mut stmt = self.conn.prepare("SELECT title, rating FROM ratings")?;
         let ratings = stmt.query_map([], 
@@ -128,30 +477,46 @@ impl Database {
         ratings.collect()
     }
 
+    /// Every stored movie with its tracks, parts and genres, by hash
     pub fn fetch_movies(&self) -> Result<HashMap<u32, Movie>, rusqlite::Error> {
         let mut stmt = self.conn.prepare("SELECT * FROM movies")?;
 
-        let existing = stmt
+        let mut existing = stmt
             .query_map([], |row| {
                 Ok(Movie {
                     title: row.get("title")?,
                     year: row.get("year")?,
+                    edition: row.get("edition")?,
+                    imdb: row.get("imdb_id")?,
+                    pattern: row.get("name_pattern")?,
+                    original_title: row.get("original_title")?,
+                    tmdb: row.get("tmdb_id")?,
+                    genres: Vec::new(),
+                    runtime: row.get("runtime")?,
+                    user_rating: row.get("user_rating")?,
                     rating: row.get("rating")?,
                     duration: row.get("duration")?,
                     video: VideoStream {
                         resolution: row.get("resolution")?,
                         codec: row.get("vid_codec")?,
                         bit_depth: row.get("bit_depth")?,
+                        hdr: row.get::<_, Option<_>>("hdr")?.unwrap_or_default(),
                     },
                     audio: AudioStream {
                         codec: row.get("aud_codec")?,
                         channels: row.get("channels")?,
                         count: row.get("aud_count")?,
+                        tracks: Vec::new(),
                     },
                     subs: SubtitleStream {
                         format: row.get("sub_format")?,
                         count: row.get("sub_count")?,
+                        tracks: Vec::new(),
                     },
+                    source: row.get("source")?,
+                    group: row.get("release_group")?,
+                    parts: Vec::new(),
+                    collection: row.get("collection")?,
                     hash: row.get("hash")?,
                     size: row.get("size")?,
                     // path: PathBuf::new(),
@@ -161,18 +526,259 @@ impl Database {
             .map(|movie| (movie.hash, movie))
             .collect::<HashMap<u32, Movie>>();
 
+        for (hash, genre) in self.fetch_genres()? {
+            if let Some(movie) = existing.get_mut(&hash) {
+                movie.genres.push(genre);
+            }
+        }
+
+        let mut stmt = self.conn.prepare("SELECT * FROM audio_tracks ORDER BY hash, idx")?;
+        let audio_tracks = stmt.query_map([], |row| {
+            Ok((row.get::<_, u32>("hash")?, AudioTrack {
+                codec: row.get("codec")?,
+                channels: row.get("channels")?,
+                language: row.get("language")?,
+            }))
+        })?;
+        for (hash, track) in audio_tracks.filter_map(Result::ok) {
+            if let Some(movie) = existing.get_mut(&hash) {
+                movie.audio.tracks.push(track);
+            }
+        }
+
+        let mut stmt = self.conn.prepare("SELECT * FROM subtitle_tracks ORDER BY hash, idx")?;
+        let sub_tracks = stmt.query_map([], |row| {
+            Ok((row.get::<_, u32>("hash")?, SubtitleTrack {
+                format: row.get("format")?,
+                language: row.get("language")?,
+                forced: row.get("forced")?,
+                file: row.get("file")?,
+            }))
+        })?;
+        for (hash, track) in sub_tracks.filter_map(Result::ok) {
+            if let Some(movie) = existing.get_mut(&hash) {
+                movie.subs.tracks.push(track);
+            }
+        }
+
+        let mut stmt = self.conn.prepare("SELECT hash, file FROM parts ORDER BY hash, idx")?;
+        let parts = stmt.query_map([], |row| Ok((row.get::<_, u32>("hash")?, row.get::<_, String>("file")?)))?;
+        for (hash, file) in parts.filter_map(Result::ok) {
+            if let Some(movie) = existing.get_mut(&hash) {
+                movie.parts.push(file);
+            }
+        }
+
         Ok(existing)
     }
+
+    /// Hash and genre of every movie that has genres
+    pub(crate) fn fetch_genres(&self) -> Result<Vec<(u32, String)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare("SELECT hash, genre FROM genres ORDER BY genre")?;
+        let genres = stmt.query_map([], |row| Ok((row.get("hash")?, row.get("genre")?)))?;
+
+        genres.collect()
+    }
 }
 
-pub fn delete_db() {
-    let db_path = dirs::config_dir().unwrap().join("numov/data.db");
-        if std::fs::metadata(&db_path).is_ok() {
-            match std::fs::remove_file(db_path) {
-                Ok(_) => println!("Successfully deleted database!"),
-                Err(e) => println!("Unable to delete database: {e}"),
-        };
-    } else {
-        println!("Database file not found.");
+/// Adds `column` to `table` if it does not exist yet, so databases
+/// created by older versions pick up new columns
+/// Schema changes that move data, each run once. `PRAGMA user_version` holds
+/// how many of them a database has had.
+fn migrate(conn: &Connection) -> Result<()> {
+    let version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+    if version < 1 {
+        // Plans used to be keyed by file hash, which changes on every rename,
+        // so carry them over to the work of each planned movie
+        let old_plan = conn
+            .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'backup_plan'")?
+            .exists([])?;
+        if old_plan {
+            let works = conn
+                .prepare(
+                    "SELECT title, year, edition, drive FROM backup_plan JOIN movies USING (hash)",
+                )?
+                .query_map([], |row| {
+                    let edition: Option<String> = row.get("edition")?;
+                    let key = Work::key_of(&row.get::<_, String>("title")?, row.get("year")?, edition.as_deref());
+                    Ok((key, row.get::<_, usize>("drive")?))
+                })?
+                .collect::<Result<Vec<_>>>()?;
+
+            let mut stmt = conn.prepare("INSERT OR IGNORE INTO backup_works (work, drive) VALUES (?, ?)")?;
+            for (work, drive) in works {
+                stmt.execute(params![work, drive])?;
+            }
+            conn.execute("DROP TABLE backup_plan", [])?;
+        }
+    }
+
+    if version < SCHEMA_VERSION {
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    }
+    Ok(())
+}
+
+/// Steps `migrate` knows
+const SCHEMA_VERSION: u32 = 1;
+
+fn add_column(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let exists = conn
+        .prepare(&format!("SELECT 1 FROM pragma_table_info('{table}') WHERE name = ?"))?
+        .exists([column])?;
+
+    if !exists {
+        conn.execute(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl}"), [])?;
+    }
+    Ok(())
+}
+
+impl Database {
+    pub(crate) fn fetch_extras(&self) -> Result<HashMap<u32, Extra>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare("SELECT * FROM extras")?;
+
+        let existing = stmt
+            .query_map([], |row| {
+                Ok(Extra {
+                    hash: row.get("hash")?,
+                    movie_hash: row.get("movie_hash")?,
+                    kind: row.get("kind")?,
+                    name: row.get("name")?,
+                    size: row.get("size")?,
+                    duration: row.get("duration")?,
+                })
+            })?
+            .filter_map(Result::ok)
+            .map(|extra| (extra.hash, extra))
+            .collect();
+
+        Ok(existing)
+    }
+
+    /// Drive capacities of the stored backup plan, and the drive of each work
+    /// (title, year and edition) on it
+    pub(crate) fn fetch_backup_plan(&self) -> Result<(Vec<f32>, HashMap<String, usize>), rusqlite::Error> {
+        let mut stmt = self.conn.prepare("SELECT capacity FROM backup_drives ORDER BY idx")?;
+        let capacities = stmt.query_map([], |row| row.get(0))?.collect::<Result<_>>()?;
+
+        let mut stmt = self.conn.prepare("SELECT work, drive FROM backup_works")?;
+        let plan = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<Result<_>>()?;
+        Ok((capacities, plan))
+    }
+
+    /// Stored checksums by path, with the size and mtime hash they were taken at
+    pub(crate) fn fetch_checksums(&self) -> Result<HashMap<PathBuf, (u32, String)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare("SELECT path, file_hash, xxh3 FROM checksums")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((PathBuf::from(row.get::<_, String>("path")?), (row.get("file_hash")?, row.get("xxh3")?)))
+        })?;
+
+        rows.collect()
+    }
+
+    /// Problems found by the last `verify` of each movie, and when it ran (local time)
+    pub(crate) fn fetch_verifications(&self) -> Result<HashMap<u32, (String, String)>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT hash, problems, datetime(verified_at, 'unixepoch', 'localtime') AS verified FROM verifications",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get("hash")?, (row.get("problems")?, row.get("verified")?)))
+        })?;
+
+        rows.collect()
+    }
+
+    pub(crate) fn fetch_episodes(&self) -> Result<HashMap<u32, Episode>, rusqlite::Error> {
+        let mut stmt = self.conn.prepare(
+            "SELECT e.*, s.year AS show_year FROM episodes e LEFT JOIN shows s ON s.title = e.show",
+        )?;
+
+        let existing = stmt
+            .query_map([], |row| {
+                Ok(Episode {
+                    show: row.get("show")?,
+                    show_year: row.get("show_year")?,
+                    season: row.get("season")?,
+                    episode: row.get("episode")?,
+                    last_episode: row.get("last_episode")?,
+                    title: row.get("title")?,
+                    size: row.get("size")?,
+                    duration: row.get("duration")?,
+                    video: VideoStream {
+                        resolution: row.get("resolution")?,
+                        codec: row.get("vid_codec")?,
+                        bit_depth: row.get("bit_depth")?,
+                        hdr: row.get("hdr")?,
+                    },
+                    audio: AudioStream {
+                        codec: row.get("aud_codec")?,
+                        channels: row.get("channels")?,
+                        count: row.get("aud_count")?,
+                        tracks: Vec::new(),
+                    },
+                    subs: SubtitleStream {
+                        format: row.get("sub_format")?,
+                        count: row.get("sub_count")?,
+                        tracks: Vec::new(),
+                    },
+                    hash: row.get("hash")?,
+                })
+            })?
+            .filter_map(Result::ok)
+            .map(|ep| (ep.hash, ep))
+            .collect();
+
+        Ok(existing)
+    }
+}
+
+/// Delete the database file at `db_path`. Returns false if there is none.
+pub fn delete_db(db_path: &Path) -> crate::error::Result<bool> {
+    match std::fs::remove_file(db_path) {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(Error::io(db_path)(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_backup_plans_are_migrated_once() {
+        let path = std::env::temp_dir().join(format!("numov-migrate-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut db = Database::open_at(&path).unwrap();
+        let mut movie = Movie::fixture("Amélie", 2001, 20.0, 7);
+        movie.edition = Some("Director's Cut".to_string());
+        db.update_movie_table([&movie], &HashSet::new()).unwrap();
+        db.update_backup_plan(&[100.0, 200.0], &[], &HashSet::new()).unwrap();
+        db.conn
+            .execute_batch(
+                "CREATE TABLE backup_plan(hash INTEGER NOT NULL PRIMARY KEY, drive INTEGER NOT NULL);
+                 INSERT INTO backup_plan VALUES (7, 1), (8, 0);
+                 PRAGMA user_version = 0;",
+            )
+            .unwrap();
+        db.close().unwrap();
+
+        let db = Database::open_at(&path).unwrap();
+        let (capacities, plan) = db.fetch_backup_plan().unwrap();
+        assert_eq!(capacities, [100.0, 200.0]);
+        assert_eq!(plan, HashMap::from([("amélie (2001) {director's cut}".to_string(), 1)]));
+        db.close().unwrap();
+
+        // Later opens leave the plan alone
+        let mut db = Database::open_at(&path).unwrap();
+        db.update_backup_plan(&[100.0, 200.0], &[("heat (1995) {}".to_string(), 0)], &HashSet::new()).unwrap();
+        db.close().unwrap();
+        let db = Database::open_at(&path).unwrap();
+        assert_eq!(db.fetch_backup_plan().unwrap().1.len(), 2);
+        db.close().unwrap();
+
+        std::fs::remove_file(&path).unwrap();
     }
 }
@@ -0,0 +1,109 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+const EDITIONS: &str = r"director'?s cut|extended(?: cut| edition)?|theatrical(?: cut| edition)?|final cut|unrated|uncut|special edition|ultimate edition|collector'?s edition|anniversary edition|remastered|criterion(?: collection)?|imax";
+
+/// Any known edition name
+pub static EDITION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!(r"(?i)\b({EDITIONS})\b")).unwrap());
+
+/// Plex style `{edition-Final Cut}`
+static PLEX_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\s*\{edition-(?P<edition>[^}]+)\}").unwrap());
+
+/// `Title - Final Cut`, `Title [Final Cut]` or `Title (Final Cut)`
+static SUFFIX_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"(?i)(?:\s+-\s+(?P<dash>{EDITIONS})\s*$|\s*[\[(](?P<bracket>{EDITIONS})[\])])"
+    ))
+    .unwrap()
+});
+
+/// Find an edition in a folder or file name, only where it is clearly
+/// marked as one so titles like `Uncut Gems` are left alone
+pub fn from_name(name: &str) -> Option<String> {
+    if let Some(c) = PLEX_RE.captures(name) {
+        return Some(c["edition"].trim().to_string());
+    }
+
+    // Only look at what comes before the year, ie. `Title - Final Cut (1982)`
+    let head = name.split(" (").next().unwrap_or(name);
+    SUFFIX_RE
+        .captures(head)
+        .or_else(|| SUFFIX_RE.captures(name))
+        .and_then(|c| c.name("dash").or(c.name("bracket")))
+        .map(|m| capitalize(m.as_str()))
+}
+
+/// Split a marked edition off of a title, ie. `Blade Runner - Final Cut`
+pub fn split(title: &str) -> (String, Option<String>) {
+    if let Some(c) = PLEX_RE.captures(title) {
+        let edition = c["edition"].trim().to_string();
+        return (PLEX_RE.replace(title, "").trim().to_string(), Some(edition));
+    }
+
+    match SUFFIX_RE.captures(title) {
+        Some(c) => {
+            let edition = c.name("dash").or(c.name("bracket")).map(|m| capitalize(m.as_str()));
+            (SUFFIX_RE.replace(title, "").trim().to_string(), edition)
+        }
+        None => (title.to_string(), None),
+    }
+}
+
+pub fn capitalize(s: &str) -> String {
+    s.split_whitespace()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(c) => c.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editions_in_names() {
+        let cases = [
+            ("Blade Runner {edition-Final Cut} (1982)", Some("Final Cut")),
+            ("Blade Runner - Final Cut (1982)", Some("Final Cut")),
+            ("Aliens [Director's Cut] (1986)", Some("Director's Cut")),
+            ("Heat (1995) (Extended Edition)", Some("Extended Edition")),
+            ("Movie - EXTENDED CUT (2001)", Some("Extended Cut")),
+            ("Uncut Gems (2019)", None),
+            ("The Final Cut (2004)", None),
+            ("Heat (1995)", None),
+        ];
+        for (name, edition) in cases {
+            assert_eq!(from_name(name).as_deref(), edition, "{name}");
+        }
+    }
+
+    #[test]
+    fn split_editions_off_titles() {
+        let cases = [
+            ("Blade Runner - Final Cut", "Blade Runner", Some("Final Cut")),
+            ("Heat {edition-Director's Cut}", "Heat", Some("Director's Cut")),
+            ("Amadeus (Director's Cut)", "Amadeus", Some("Director's Cut")),
+            ("Uncut Gems", "Uncut Gems", None),
+            ("Apocalypse Now (Redux)", "Apocalypse Now (Redux)", None),
+        ];
+        for (title, rest, edition) in cases {
+            assert_eq!(split(title), (rest.to_string(), edition.map(String::from)), "{title}");
+        }
+    }
+
+    #[test]
+    fn capitalize_words() {
+        let cases = [("DIRECTOR'S CUT", "Director's Cut"), ("imax", "Imax"), ("  final   cut ", "Final Cut"), ("", "")];
+        for (input, expected) in cases {
+            assert_eq!(capitalize(input), expected, "{input}");
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use matroska::MatroskaError;
+use polars::prelude::PolarsError;
+use std::{fmt, io, path::Path, path::PathBuf};
+
+/// Result of anything in numov that can fail
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Everything that can go wrong in numov, with what it was working on
+#[derive(Debug)]
+pub enum Error {
+    /// A file or folder could not be read or written
+    Io {
+        /// What was being read or written
+        path: PathBuf,
+        /// Why it failed
+        source: io::Error,
+    },
+    /// A file is not valid Matroska
+    Matroska {
+        /// The file that failed to parse
+        path: PathBuf,
+        /// What the parser ran into
+        source: MatroskaError,
+    },
+    /// A readable file that lacks what numov needs, such as a video track or a title
+    Unusable {
+        /// The file that was left out
+        path: PathBuf,
+        /// What it lacks
+        reason: String,
+    },
+    /// The database could not be read or written
+    Sqlite(rusqlite::Error),
+    /// A web page could not be fetched
+    Http {
+        /// Address of the page that was requested
+        url: String,
+        /// Why it could not be fetched
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// A page did not have the expected structure
+    Html {
+        /// Address of the page that was parsed
+        url: String,
+        /// What was missing or unexpected
+        reason: String,
+    },
+    /// The config file or a `.numovignore` could not be read or holds invalid settings
+    Config {
+        /// The config file or `.numovignore`
+        path: PathBuf,
+        /// What is wrong with it
+        reason: String,
+    },
+    /// The library root could not be watched for changes
+    Watch {
+        /// The library root that was to be watched
+        path: PathBuf,
+        /// Why watching it failed
+        source: notify::Error,
+    },
+    /// The platform has no user config directory to keep the config and database in
+    NoConfigDir,
+    /// A table could not be built or written
+    DataFrame(PolarsError),
+    /// JSON could not be written
+    Json(serde_json::Error),
+}
+
+impl Error {
+    /// For `map_err`: an I/O error on `path`
+    pub fn io(path: &Path) -> impl FnOnce(io::Error) -> Error + '_ {
+        move |source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    /// For `map_err`: a Matroska error reading `path`. Plain I/O errors are kept as such.
+    pub fn matroska(path: &Path) -> impl FnOnce(MatroskaError) -> Error + '_ {
+        move |source| match source {
+            MatroskaError::Io(source) => Error::io(path)(source),
+            source => Error::Matroska {
+                path: path.to_path_buf(),
+                source,
+            },
+        }
+    }
+
+    /// For `map_err`: a failure watching `path`
+    pub fn watch(path: &Path) -> impl FnOnce(notify::Error) -> Error + '_ {
+        move |source| Error::Watch {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    /// A readable file that numov cannot use, and why
+    pub fn unusable(path: &Path, reason: &str) -> Error {
+        Error::Unusable {
+            path: path.to_path_buf(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Process exit code, so scripts can tell failures apart.
+    /// 1 is left for commands that found problems, 2 for invalid arguments.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Io { .. } => 3,
+            Error::Matroska { .. } => 4,
+            Error::Sqlite(_) => 5,
+            Error::Http { .. } => 6,
+            Error::Html { .. } => 7,
+            Error::Config { .. } | Error::NoConfigDir => 8,
+            Error::DataFrame(_) | Error::Json(_) => 9,
+            Error::Unusable { .. } => 10,
+            Error::Watch { .. } => 11,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Error::Matroska { path, source } => write!(f, "{}: {source}", path.display()),
+            Error::Unusable { path, reason } => write!(f, "{}: {reason}", path.display()),
+            Error::Watch { path, source } => write!(f, "Could not watch {}: {source}", path.display()),
+            Error::NoConfigDir => write!(f, "Could not find the user config directory"),
+            Error::Sqlite(e) => write!(f, "Database error: {e}"),
+            Error::Http { url, source } => write!(f, "Request to {url} failed: {source}"),
+            Error::Html { url, reason } => write!(f, "Unexpected page at {url}: {reason}"),
+            Error::Config { path, reason } => write!(f, "Invalid config at {}\n{reason}", path.display()),
+            Error::DataFrame(e) => write!(f, "Could not build table: {e}"),
+            Error::Json(e) => write!(f, "Could not write JSON: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Matroska { source, .. } => Some(source),
+            Error::Sqlite(e) => Some(e),
+            Error::Http { source, .. } => Some(source.as_ref()),
+            Error::DataFrame(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::Watch { source, .. } => Some(source),
+            Error::Html { .. } | Error::Config { .. } | Error::Unusable { .. } | Error::NoConfigDir => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Sqlite(e)
+    }
+}
+
+impl From<PolarsError> for Error {
+    fn from(e: PolarsError) -> Self {
+        Error::DataFrame(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
@@ -0,0 +1,136 @@
+use crate::error::{Error, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+pub const IGNORE_FILE: &str = ".numovignore";
+
+/// gitignore-style rules from `scan.exclude` in the config and from
+/// `.numovignore` files at the root and in its subfolders
+#[derive(Debug, Clone)]
+pub struct Exclusions {
+    /// Rules and the folder they apply to, shallowest first
+    rules: Vec<(PathBuf, Gitignore)>,
+    /// How deep below the root `.numovignore` files were read
+    depth: usize,
+}
+
+impl Exclusions {
+    /// Read the rules of `excludes` and of the `.numovignore` files down to `max_depth`.
+    /// Fails on the first invalid rule, naming the config or the file it is in.
+    pub fn load(root: &Path, excludes: &[String], max_depth: usize) -> Result<Self> {
+        let invalid = |path: PathBuf| move |e: ignore::Error| Error::Config { path, reason: e.to_string() };
+        let config_path = || crate::config::Config::path().unwrap_or_else(|_| PathBuf::from("scan.exclude"));
+
+        let mut builder = GitignoreBuilder::new(root);
+        for glob in excludes {
+            builder.add_line(None, glob).map_err(invalid(config_path()))?;
+        }
+        let mut rules = vec![(root.to_path_buf(), builder.build().map_err(invalid(config_path()))?)];
+
+        // WalkDir yields parents before children, so deeper files come later
+        for entry in WalkDir::new(root)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name() == IGNORE_FILE)
+        {
+            let dir = entry.path().parent().unwrap_or(root);
+            let (file, error) = Gitignore::new(entry.path());
+            if let Some(e) = error {
+                return Err(invalid(entry.path().to_path_buf())(e));
+            }
+            rules.push((dir.to_path_buf(), file));
+        }
+
+        Ok(Exclusions { rules, depth: max_depth })
+    }
+
+    /// Whether these rules hold every `.numovignore` a walk to `max_depth` could meet
+    pub fn covers(&self, max_depth: usize) -> bool {
+        self.depth >= max_depth
+    }
+
+    /// Why a path is excluded, or `None` if it is not. Rules from deeper
+    /// folders win, and a `!pattern` re-includes what an earlier rule excluded.
+    pub fn reason(&self, path: &Path, is_dir: bool) -> Option<String> {
+        let mut reason = None;
+        for (_, rules) in self.rules.iter().filter(|(dir, _)| path.starts_with(dir)) {
+            match rules.matched(path, is_dir) {
+                Match::Ignore(glob) => {
+                    reason = Some(match glob.from() {
+                        Some(file) => format!("'{}' in {}", glob.original(), file.display()),
+                        None => format!("'{}' in scan.exclude", glob.original()),
+                    })
+                }
+                Match::Whitelist(_) => reason = None,
+                Match::None => {}
+            }
+        }
+        reason
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A library with `.numovignore` files at the root and in `Collection`
+    fn library(name: &str, root_rules: &str, collection_rules: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("numov-exclude-{name}-{}", std::process::id()));
+        fs::create_dir_all(root.join("Collection")).unwrap();
+        fs::write(root.join(IGNORE_FILE), root_rules).unwrap();
+        fs::write(root.join("Collection").join(IGNORE_FILE), collection_rules).unwrap();
+        root
+    }
+
+    #[test]
+    fn deeper_files_override_shallower_ones() {
+        let root = library("deeper", "Samples/\n", "!Samples/\nTrailers/\n");
+        let rules = Exclusions::load(&root, &[], 2).unwrap();
+
+        let reason = rules.reason(&root.join("Samples"), true).unwrap();
+        assert!(reason.starts_with("'Samples/' in ") && reason.ends_with(IGNORE_FILE));
+        assert_eq!(rules.reason(&root.join("Collection/Samples"), true), None);
+        assert!(rules.reason(&root.join("Collection/Trailers"), true).is_some());
+        assert_eq!(rules.reason(&root.join("Trailers"), true), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn numovignore_files_can_re_include_what_the_config_excludes() {
+        let root = library("config", "", "!*.sample.mkv\n");
+        let rules = Exclusions::load(&root, &["*.sample.mkv".to_string()], 2).unwrap();
+
+        assert_eq!(
+            rules.reason(&root.join("Heat (1995)/Heat.sample.mkv"), false).as_deref(),
+            Some("'*.sample.mkv' in scan.exclude")
+        );
+        assert_eq!(rules.reason(&root.join("Collection/Alien (1979)/Alien.sample.mkv"), false), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn files_below_the_depth_are_not_read() {
+        let root = library("depth", "", "Extras/\n");
+        let shallow = Exclusions::load(&root, &[], 1).unwrap();
+        let deep = Exclusions::load(&root, &[], 2).unwrap();
+
+        assert_eq!(shallow.reason(&root.join("Collection/Extras"), true), None);
+        assert!(!shallow.covers(2));
+        assert!(deep.reason(&root.join("Collection/Extras"), true).is_some());
+        assert!(deep.covers(2));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn an_invalid_rule_names_its_file() {
+        let root = library("invalid", "", "Extras/{a,b\n");
+        let err = Exclusions::load(&root, &[], 2).unwrap_err();
+
+        assert!(matches!(err, Error::Config { ref path, .. } if *path == root.join("Collection").join(IGNORE_FILE)));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
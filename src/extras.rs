@@ -0,0 +1,69 @@
+use crate::{error, movie::Movie, movie_types::extra_kind::ExtraKind};
+use core::time::Duration;
+use regex::Regex;
+use std::{
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+
+/// Plex style `-trailer` suffixes, or a bare `sample`/`trailer` word in the name
+static NAME_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(?:-(?P<suffix>trailer|sample|featurette|behindthescenes|deleted|interview|scene|short|other)$|(?:^|[ ._-])(?P<word>sample|trailer)(?:[ ._-]|$))",
+    )
+    .unwrap()
+});
+
+/// A trailer, sample or other bonus file that belongs to a movie
+#[derive(Debug)]
+pub struct Extra {
+    pub hash: u32,
+    /// Hash of the main feature in the same folder, if there is one
+    pub movie_hash: Option<u32>,
+    pub kind: ExtraKind,
+    pub name: String,
+    pub size: f32,
+    pub duration: String,
+}
+
+impl Extra {
+    pub fn new(path: &Path, kind: ExtraKind, duration: Option<Duration>) -> error::Result<Self> {
+        let (bytes, hash) = Movie::read_metadata(path)?;
+
+        Ok(Extra {
+            hash,
+            movie_hash: None,
+            kind,
+            name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            size: Movie::make_gb(bytes),
+            duration: Movie::readable_duration(&duration.unwrap_or_default()),
+        })
+    }
+
+    /// Detect an extra by its file name or the extras folder it sits in
+    pub fn detect(path: &Path) -> Option<ExtraKind> {
+        let folder = path.parent()?.file_name()?.to_str()?;
+        if let Some(kind) = ExtraKind::from_folder(folder) {
+            return Some(kind);
+        }
+
+        let stem = path.file_stem()?.to_str()?;
+        let captures = NAME_RE.captures(stem)?;
+        match (captures.name("suffix"), captures.name("word")) {
+            (Some(suffix), _) => ExtraKind::from_suffix(suffix.as_str()),
+            (None, Some(word)) => ExtraKind::from_suffix(word.as_str()),
+            _ => None,
+        }
+    }
+
+    /// The movie folder a file belongs to, skipping over an extras folder
+    pub fn movie_dir(path: &Path) -> PathBuf {
+        let parent = path.parent().unwrap_or(path);
+        match parent.file_name().and_then(|n| n.to_str()) {
+            Some(name) if ExtraKind::from_folder(name).is_some() => {
+                parent.parent().unwrap_or(parent).to_path_buf()
+            }
+            _ => parent.to_path_buf(),
+        }
+    }
+}
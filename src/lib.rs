@@ -0,0 +1,87 @@
+//! Numov reads libraries of matroska files into a SQLite database, and
+//! reports on, renames and checks them.
+//!
+//! The `numov` binary is one user of this crate. Other tools can use the same
+//! types to probe a single file, open a library and its database, run scans
+//! and query the movies found:
+//!
+//! ```no_run
+//! use numov::{Config, Database, Library, Movie, Query, ScanEvent};
+//! use std::path::{Path, PathBuf};
+//!
+//! # fn main() -> numov::Result<()> {
+//! // One file, without a library
+//! let movie = Movie::probe(Path::new("/movies/Heat (1995)/Heat.mkv"), &Config::default(), &mut |_| {})?;
+//! println!("{} is {}", movie.display_name(), movie.video.resolution);
+//!
+//! // A library with its own database
+//! let db = Database::open_at(Path::new("/tmp/movies.db"))?;
+//! let mut lib = Library::from_parts(PathBuf::from("/movies"), db, Config::load()?)?;
+//! let report = lib.scan(|event| {
+//!     if let ScanEvent::Skipped(e) = event {
+//!         eprintln!("{e}");
+//!     }
+//! })?;
+//! println!("{} new movies", report.added.len());
+//!
+//! let query = Query {
+//!     genre: Some("Crime".to_string()),
+//!     ..Default::default()
+//! };
+//! for movie in lib.query(&query) {
+//!     println!("{} ({})", movie.title, movie.year);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Everything that can fail returns [`Error`], which says what was being read
+//! and maps to the exit codes of the CLI.
+
+#![warn(missing_docs)]
+
+mod backup;
+mod checksum;
+#[cfg(test)]
+#[path = "../tests/common/mod.rs"]
+mod ebml;
+mod compare;
+/// Reading `config.toml` and its profiles
+pub mod config;
+/// The SQLite database a library is stored in
+pub mod database;
+mod edition;
+/// The crate-wide error type
+pub mod error;
+mod exclude;
+mod extras;
+/// Libraries: scans, queries and reports
+pub mod library;
+mod lint;
+/// Reading a single movie file
+pub mod movie;
+/// Enums describing tracks and releases
+pub mod movie_types;
+mod nfo;
+mod pattern;
+/// Reading Matroska headers without the clusters or attachments
+pub mod probe;
+mod release;
+mod sidecar;
+mod stack;
+mod tv;
+mod verify;
+mod work;
+
+pub use config::Config;
+pub use database::Database;
+pub use error::{Error, Result};
+pub use compare::{Difference, Side, Status};
+pub use library::{
+    BackupPlan, Comparison, DriveUse, FsckReport, Library, NfoExport, PlannedFilm, ProbeBench, Query, RatingsReport,
+    ScanEvent, ScanReport, VerifyReport,
+};
+pub use lint::{Issue, Problem};
+pub use movie::Movie;
+pub use nfo::Overwrite;
+pub use verify::Verification;
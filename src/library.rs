@@ -1,84 +1,536 @@
-use crate::{database::Database, movie::Movie};
+use crate::{
+    backup,
+    checksum::{self, Hasher},
+    compare::{self, Difference},
+    config::Config,
+    database::Database,
+    error::{Error, Result},
+    exclude::{Exclusions, IGNORE_FILE},
+    extras::Extra,
+    lint::{self, Issue},
+    movie::Movie,
+    movie_types::extra_kind::ExtraKind,
+    nfo::{Nfo, Overwrite},
+    probe, sidecar,
+    stack,
+    tv::{Episode, SeasonReport},
+    verify::{self, Verification},
+    work::Work,
+};
 use polars::prelude::*;
-use rusqlite::Result;
 use select::{
     document::Document,
     predicate::{Attr, Class},
 };
 use std::{
-    collections::{HashMap, HashSet},
-    env,
-    io::Stdout,
-    iter::repeat,
-    path::PathBuf,
-    time::Instant,
-    u32,
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant},
 };
+use notify::{RecursiveMode, Watcher};
 use walkdir::WalkDir;
 
+/// A movie library: the movies, extras and episodes under `root`, as stored in its database
 #[derive(Debug)]
 pub struct Library {
+    /// Where the library is stored
     pub db: Database,
+    /// Folder the movies are read from
     pub root: PathBuf,
-    legacy_collection: HashSet<u32>,
+    /// Only output movies of this genre
+    pub genre: Option<String>,
+    /// Only output the best version of each work
+    pub best_only: bool,
+    config: Config,
     collection: HashMap<u32, Movie>,
+    extras: HashMap<u32, Extra>,
+    episodes: HashMap<u32, Episode>,
+    /// Changes not written to the database yet
+    dirty: Dirty,
+    /// Hashes of the movies and extras in each movie folder seen by the last scan
+    folders: HashMap<PathBuf, Vec<u32>>,
     ratings: HashMap<String, String>,
+    /// Size and mtime hash of each file when its content checksum was stored
+    checksums: HashMap<PathBuf, u32>,
+    /// Background hashing, started once a file needs a checksum
+    hasher: Option<Hasher>,
+    /// Exclusions kept for a watch session, so each rescan does not read them again
+    exclusions: Option<Exclusions>,
 }
 
-const DATAFRAME_LEN: usize = 20;
+/// Which movies `Library::query` returns. The default matches every movie.
+#[derive(Debug, Default, Clone)]
+pub struct Query {
+    /// Part of the title, in any case
+    pub title: Option<String>,
+    /// Release year
+    pub year: Option<i16>,
+    /// Genre, as read from nfo files
+    pub genre: Option<String>,
+    /// Only the best version of each work
+    pub best_only: bool,
+}
 
-impl Library {
-    pub fn new(root: PathBuf) -> Self {
-        let db = Database::open().unwrap_or_else(|e| {
-            eprintln!("Could not make database!\nError: {e}");
-            std::process::exit(1);
-        });
+/// Reported while a scan, or another long task of the library, runs
+#[derive(Debug)]
+pub enum ScanEvent<'a> {
+    /// `done` of the `total` files (or pages) of the task have been looked at
+    Progress {
+        /// Files looked at so far
+        done: usize,
+        /// Files the task looks at
+        total: usize,
+    },
+    /// A file that could not be read, and was left out
+    Skipped(&'a Error),
+    /// A problem that did not stop the task, such as a broken nfo or exclude rule
+    Warning(&'a Error),
+    /// The title of a file, guessed from its name, was written into its metadata
+    TitleWritten {
+        /// The MKV whose title tag was set
+        path: &'a Path,
+        /// The title written
+        title: &'a str,
+    },
+    /// A movie folder was renamed
+    Renamed {
+        /// Old folder
+        from: &'a Path,
+        /// New folder
+        to: &'a Path,
+    },
+    /// `Library::watch` rescanned the folders that changed
+    Rescanned(&'a ScanReport),
+}
+
+/// What a scan changed. Titles are display names, sorted.
+#[derive(Debug, Default)]
+pub struct ScanReport {
+    /// Movies new to the library
+    pub added: Vec<String>,
+    /// Movies whose file changed
+    pub updated: Vec<String>,
+    /// Movies whose file is gone
+    pub removed: Vec<String>,
+    /// Extras added or re-linked
+    pub extras_saved: usize,
+    /// Extras whose file is gone
+    pub extras_removed: usize,
+}
+
+impl ScanReport {
+    /// Whether the scan found no movies added, changed or removed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// What `Library::fsck` found
+#[derive(Debug, Default)]
+pub struct FsckReport {
+    /// Files whose content changed while their size and mtime did not, relative to the root
+    pub changed: Vec<String>,
+    /// Files hashed against a stored checksum
+    pub checked: usize,
+    /// Files that got their first checksum for their size and mtime
+    pub first: usize,
+}
+
+/// What `Library::verify` found
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// Movies in the library, whose results were stored
+    pub results: Vec<Verification>,
+    /// Films not scanned yet, checked but not stored
+    pub unscanned: Vec<Verification>,
+}
+
+impl VerifyReport {
+    /// The damaged films, scanned or not
+    pub fn damaged(&self) -> impl Iterator<Item = &Verification> {
+        self.results.iter().chain(&self.unscanned).filter(|v| !v.ok())
+    }
+}
+
+/// What `Library::compare` found
+#[derive(Debug, Default)]
+pub struct Comparison {
+    /// Films with the same content under both roots
+    pub identical: usize,
+    /// How the other films differ
+    pub differences: Vec<Difference>,
+}
+
+/// A backup plan made by `Library::plan_backup`
+#[derive(Debug, Default)]
+pub struct BackupPlan {
+    /// Each drive and how full the plan leaves it
+    pub drives: Vec<DriveUse>,
+    /// Films placed by this plan, by drive
+    pub placed: Vec<PlannedFilm>,
+    /// New versions of planned works that no longer fit on their work's drive
+    pub overflow: Vec<PlannedFilm>,
+    /// Films that did not fit on any drive; their `drive` is `None`
+    pub unplaced: Vec<PlannedFilm>,
+}
 
-        let (collection, ratings) = db.fetch();
-        if !collection.is_empty() || !ratings.is_empty() {
-            println!(
-                "Read in {} movies and {} ratings from database.",
-                collection.len(),
-                ratings.len()
-            );
+/// One backup drive in a `BackupPlan`, in GB
+#[derive(Debug)]
+pub struct DriveUse {
+    /// Size of the drive
+    pub capacity: f32,
+    /// Space the planned films take
+    pub used: f32,
+    /// Films planned on it
+    pub films: u32,
+    /// Of those, films placed by this plan
+    pub new: u32,
+}
+
+/// A film in a `BackupPlan`
+#[derive(Debug)]
+pub struct PlannedFilm {
+    /// Display name of the movie
+    pub movie: String,
+    /// GB
+    pub size: f32,
+    /// Index of its drive
+    pub drive: Option<usize>,
+    /// Its collection folder
+    pub collection: Option<String>,
+}
+
+impl PlannedFilm {
+    fn new(movie: &Movie, drive: Option<usize>) -> Self {
+        PlannedFilm {
+            movie: movie.display_name(),
+            size: movie.size,
+            drive,
+            collection: movie.collection.clone(),
         }
-        let legacy_collection = collection.keys().cloned().collect::<HashSet<u32>>();
+    }
+}
+
+/// Bytes read and time taken for one file by `Library::bench_probe`
+#[derive(Debug)]
+pub struct ProbeBench {
+    /// File name
+    pub file: String,
+    /// Bytes the header-only probe read
+    pub probe_bytes: u64,
+    /// Time the probe took
+    pub probe_time: Duration,
+    /// Bytes `Matroska::open` read
+    pub open_bytes: u64,
+    /// Time `Matroska::open` took
+    pub open_time: Duration,
+}
 
-        Library {
+/// Ratings stored and mapped by `Library::update_ratings`
+#[derive(Debug, Default)]
+pub struct RatingsReport {
+    /// Ratings new or changed since the last scrape
+    pub changed: usize,
+    /// Movies with a rating
+    pub mapped: usize,
+}
+
+/// The nfo files written, or to be written, by `Library::export_nfo`
+#[derive(Debug, Default)]
+pub struct NfoExport {
+    /// Files written
+    pub written: Vec<PathBuf>,
+    /// Existing files kept by the overwrite policy
+    pub kept: usize,
+}
+
+impl Library {
+    /// Open the library at `root` with the config file and the database it names
+    pub fn new(root: PathBuf) -> Result<Self> {
+        let config = Config::load()?;
+        Self::from_parts(root, Database::open_at(&config.db_path()?)?, config)
+    }
+
+    /// Open the library at `root`, stored in `db` and scanned with `config`
+    pub fn from_parts(root: PathBuf, db: Database, config: Config) -> Result<Self> {
+        let (collection, ratings) = db.fetch()?;
+        let extras = db.fetch_extras()?;
+        let episodes = db.fetch_episodes()?;
+        let checksums = db
+            .fetch_checksums()?
+            .into_iter()
+            .map(|(path, (file_hash, _))| (path, file_hash))
+            .collect();
+
+        Ok(Library {
             db,
             root,
+            genre: None,
+            best_only: false,
+            config,
             ratings,
             collection,
-            legacy_collection,
-        }
+            extras,
+            episodes,
+            dirty: Dirty::default(),
+            folders: HashMap::new(),
+            checksums,
+            hasher: None,
+            exclusions: None,
+        })
     }
 
-    // For each .mkv in the path_list
-    //  Generate hash, and try to remove it from hashet
+    /// The config the library was opened with
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Close the library's database
+    pub fn close(self) -> Result<()> {
+        self.db.close()
+    }
+
+    /// Every movie in the library
+    pub fn movies(&self) -> impl Iterator<Item = &Movie> {
+        self.collection.values()
+    }
+
+    /// Number of works (title, year and edition) the movies are versions of
+    pub fn work_count(&self) -> usize {
+        Work::group(self.collection.values()).len()
+    }
+
+    /// Number of letterboxd ratings stored
+    pub fn rating_count(&self) -> usize {
+        self.ratings.len()
+    }
+
+    /// The movie with this hash, see `Movie::read_metadata`
+    pub fn movie(&self, hash: u32) -> Option<&Movie> {
+        self.collection.get(&hash)
+    }
+
+    /// The movies matching `query`, by title and year
+    pub fn query(&self, query: &Query) -> Vec<&Movie> {
+        let movies: Vec<&Movie> = match query.best_only {
+            true => Work::group(self.collection.values()).iter().map(Work::best).collect(),
+            false => self.collection.values().collect(),
+        };
+        let title = query.title.as_ref().map(|t| t.to_lowercase());
+
+        let mut movies = movies
+            .into_iter()
+            .filter(|m| title.as_ref().is_none_or(|t| m.title.to_lowercase().contains(t)))
+            .filter(|m| query.year.is_none_or(|y| m.year == y))
+            .filter(|m| match &query.genre {
+                Some(g) => m.genres.iter().any(|mg| mg.eq_ignore_ascii_case(g)),
+                None => true,
+            })
+            .collect::<Vec<_>>();
+        movies.sort_by(|a, b| (&a.title, a.year).cmp(&(&b.title, b.year)));
+        movies
+    }
+
+    /// The paths under the root that scans leave out, and the exclude rule behind each.
+    /// Nothing below an excluded folder is listed.
+    pub fn excluded(&self) -> Result<Vec<(PathBuf, String)>> {
+        let max_depth = self.config.scan.max_depth + 1;
+        let exclusions = self._exclusions(max_depth)?;
+        let mut excluded = Vec::new();
+        let walk = WalkDir::new(&self.root).max_depth(max_depth).into_iter().filter_entry(|e| {
+            match exclusions.reason(e.path(), e.file_type().is_dir()) {
+                Some(reason) => {
+                    excluded.push((e.path().to_path_buf(), reason));
+                    false
+                }
+                None => true,
+            }
+        });
+        walk.for_each(drop);
+        Ok(excluded)
+    }
+
+    // For each movie folder, and each .mkv in it
+    //  Generate hash, and try to remove it from the known movies/extras
     //  If it cannot be removed:
-    //      Create a new movie instance, and add it to collection
-    pub fn update_movies(&mut self) {
+    //      Detect extras by name or folder, otherwise open it as a candidate
+    //  Sort the candidates into a main feature and short extras, and add them to the collection
+    /// Scan the movies under the root and store what changed, calling `on_event` as it goes
+    pub fn scan(&mut self, mut on_event: impl FnMut(ScanEvent)) -> Result<ScanReport> {
+        let path_list = self._get_dirs()?;
         let mut logger = Logger::new();
-        let path_list = Self::_get_dirs(&self.root);
+        let mut legacy = self.collection.keys().cloned().collect::<HashSet<u32>>();
+        let mut legacy_extras = self.extras.keys().cloned().collect::<HashSet<u32>>();
+        let mut extras_saved = 0;
+
+        let (mut done, total) = (0, path_list.len());
+        on_event(ScanEvent::Progress { done, total });
+        for (dir, paths) in Self::group_by_movie_dir(&path_list) {
+            self.scan_movie_dir(dir, &paths, &mut legacy, &mut legacy_extras, &mut logger, &mut on_event);
+            done += paths.len();
+            on_event(ScanEvent::Progress { done, total });
+
+            // Save progress as the walk goes, so an interrupted scan can resume. Nothing
+            // is removed until the walk is done, as unvisited movies would look deleted.
+            if self.dirty.movies.len() + self.dirty.extras.len() >= self.config.scan.batch_size.max(1) {
+                extras_saved += self.save()?.0;
+            }
+        }
+
+        self.drop_leftovers(&legacy, &legacy_extras, &mut logger);
+        let (extras, extras_removed) = self.save()?;
+        Ok(logger.report(extras_saved + extras, extras_removed))
+    }
+
+    /// Scan one movie folder. Movies and extras that are found again are taken
+    /// out of `legacy` and `legacy_extras`; new ones are added to the library.
+    fn scan_movie_dir(
+        &mut self,
+        dir: PathBuf,
+        paths: &[&PathBuf],
+        legacy: &mut HashSet<u32>,
+        legacy_extras: &mut HashSet<u32>,
+        logger: &mut Logger,
+        on_event: &mut dyn FnMut(ScanEvent),
+    ) {
+        let mut known = Vec::new();
+        let mut candidates = Vec::new();
+        let mut extras = Vec::new();
+
+        // Extras are always single files; everything else may be a stacked film
+        let mut movie_paths = Vec::new();
+        for path in paths {
+            self.queue_checksum(path);
+            let Some(kind) = Extra::detect(path) else {
+                movie_paths.push(*path);
+                continue;
+            };
+            let duration = || probe::open(path, self.config.scan.read_budget()).ok()?.matroska.info.duration;
+            let extra = Movie::read_metadata(path).and_then(|(_, hash)| match legacy_extras.remove(&hash) {
+                true => Ok((hash, None)),
+                false => Ok((hash, Some(Extra::new(path, kind, duration())?))),
+            });
+            match extra {
+                Ok((hash, new)) => {
+                    if let Some(extra) = new {
+                        self.dirty.extras.insert(hash);
+                        self.extras.insert(hash, extra);
+                    }
+                    extras.push(hash);
+                }
+                Err(e) => on_event(ScanEvent::Skipped(&e)),
+            }
+        }
+
+        for unit in stack::group(movie_paths) {
+            let hash = match stack::hash(&unit) {
+                Ok(hash) => hash,
+                Err(e) => {
+                    on_event(ScanEvent::Skipped(&e));
+                    continue;
+                }
+            };
+            let found = match legacy.remove(&hash) {
+                true => self.collection.get_mut(&hash),
+                false => None,
+            };
+            if let Some(movie) = found {
+                if movie.subs.set_external(sidecar::find(&unit)) {
+                    logger.updated.insert(movie.display_name());
+                    self.dirty.movies.insert(hash);
+                }
+                known.push(hash);
+                continue;
+            } else if unit.len() == 1 && legacy_extras.remove(&hash) {
+                extras.push(hash);
+                continue;
+            }
+
+            let parts = unit
+                .into_iter()
+                .filter_map(|path| match probe::open(path, self.config.scan.read_budget()) {
+                    Ok(probe) => Some((path, probe.matroska)),
+                    Err(e) => {
+                        on_event(ScanEvent::Skipped(&e));
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            if !parts.is_empty() {
+                candidates.push((hash, parts));
+            }
+        }
 
-        let mut main_prog = Prog::new(path_list.len(), "updated library");
-        for path in &path_list {
-            let hash = Movie::read_metadata(path).1;
-            if !self.legacy_collection.remove(&hash) {
-                let movie = Movie::new(path);
-                logger
-                    .new
-                    .insert(format!("{} ({})", &movie.title, movie.year));
-                self.collection.insert(hash, movie);
+        // The longest film in the folder is the main feature
+        let max_secs = self.config.extras.max_minutes * 60;
+        let secs = |parts: &[(&PathBuf, matroska::Matroska)]| {
+            parts.iter().map(|(_, mkv)| mkv.info.duration.unwrap_or_default().as_secs()).sum::<u64>()
+        };
+        let main = known
+            .iter()
+            .map(|h| (*h, self.collection[h].runtime_minutes() as u64 * 60))
+            .chain(candidates.iter().map(|(h, parts)| (*h, secs(parts))))
+            .max_by_key(|(_, s)| *s);
+
+        for (hash, mut parts) in candidates {
+            let is_short = parts.len() == 1 && secs(&parts) < max_secs;
+            match (main, parts.pop()) {
+                (Some((main_hash, main_secs)), Some((path, mkv)))
+                    if hash != main_hash && is_short && main_secs >= max_secs =>
+                {
+                    match Extra::new(path, ExtraKind::Other, mkv.info.duration) {
+                        Ok(extra) => {
+                            extras.push(hash);
+                            self.dirty.extras.insert(hash);
+                            self.extras.insert(hash, extra);
+                        }
+                        Err(e) => on_event(ScanEvent::Skipped(&e)),
+                    }
+                }
+                (_, last) => {
+                    parts.extend(last);
+                    let mut movie = match Movie::collect_parts(&parts, hash, &self.config, true, on_event) {
+                        Ok(movie) => movie,
+                        Err(e) => {
+                            on_event(ScanEvent::Skipped(&e));
+                            continue;
+                        }
+                    };
+                    movie.collection = self.collection_of(parts[0].0);
+                    logger.new.insert(movie.display_name());
+                    self.dirty.movies.insert(hash);
+                    known.push(hash);
+                    self.collection.insert(hash, movie);
+                }
             }
-            main_prog.inc();
         }
-        main_prog.end();
+
+        // Link (or re-link) the folder's extras to its main feature
+        let main_hash = main.map(|(h, _)| h);
+        for hash in &extras {
+            if let Some(extra) = self.extras.get_mut(hash) {
+                if extra.movie_hash != main_hash {
+                    extra.movie_hash = main_hash;
+                    self.dirty.extras.insert(*hash);
+                }
+            }
+        }
+
+        known.extend(extras);
+        self.folders.insert(dir, known);
+    }
+
+    /// Take the movies and extras that were not found again out of the library,
+    /// and map ratings onto the new ones
+    fn drop_leftovers(&mut self, legacy: &HashSet<u32>, legacy_extras: &HashSet<u32>, logger: &mut Logger) {
         self.map_ratings();
 
-        self.legacy_collection.iter().for_each(|leftover| {
+        for leftover in legacy {
             if let Some(m) = self.collection.remove(leftover) {
-                let name = format!("{} ({})", &m.title, &m.year);
+                self.dirty.removed.insert(*leftover);
+                let name = m.display_name();
                 match logger.new.get(&name) {
                     Some(_) => {
                         logger.new.remove(&name);
@@ -87,86 +539,436 @@ impl Library {
                     None => logger.removed.insert(name),
                 };
             }
-        });
+        }
+
+        for leftover in legacy_extras {
+            if self.extras.remove(leftover).is_some() {
+                self.dirty.removed_extras.insert(*leftover);
+            }
+        }
+    }
+
+    /// Write the movies and extras changed since the last save, and delete the removed ones.
+    /// Nothing is written, and no transaction started, if nothing changed.
+    /// Returns the number of extras written and removed. On failure, the changes
+    /// stay marked for the next save.
+    fn save(&mut self) -> Result<(usize, usize)> {
+        let dirty = std::mem::take(&mut self.dirty);
+        // A hash that came back (a folder renamed back and forth) is an update, not a removal
+        let removed = dirty
+            .removed
+            .iter()
+            .filter(|h| !self.collection.contains_key(h))
+            .copied()
+            .collect::<HashSet<_>>();
+        let removed_extras = dirty
+            .removed_extras
+            .iter()
+            .filter(|h| !self.extras.contains_key(h))
+            .copied()
+            .collect::<HashSet<_>>();
+
+        if !dirty.movies.is_empty() || !removed.is_empty() {
+            let movies = dirty.movies.iter().filter_map(|h| self.collection.get(h));
+            if let Err(e) = self.db.update_movie_table(movies, &removed) {
+                self.dirty = dirty;
+                return Err(e.into());
+            }
+        }
+
+        if dirty.extras.is_empty() && removed_extras.is_empty() {
+            return Ok((0, 0));
+        }
+        let extras = dirty.extras.iter().filter_map(|h| self.extras.get(h)).collect::<Vec<_>>();
+        match self.db.update_extras_table(&extras, &removed_extras) {
+            Ok(_) => Ok((extras.len(), removed_extras.len())),
+            Err(e) => {
+                self.dirty.extras.extend(dirty.extras);
+                self.dirty.removed_extras.extend(dirty.removed_extras);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Given a `user_name` (String) from letterboxd, scrape ratings, store in database
+    /// and map them onto the movies in it. Pages scraped are reported to `on_event`.
+    pub fn update_ratings(
+        &mut self,
+        user_name: &impl AsRef<str>,
+        mut on_event: impl FnMut(ScanEvent),
+    ) -> Result<RatingsReport> {
+        let ratings = Self::retrieve_ratings(user_name.as_ref(), &mut on_event)?;
+        let changed = ratings
+            .iter()
+            .filter(|(title, rating)| self.ratings.get(*title) != Some(*rating))
+            .map(|(title, rating)| (title.clone(), rating.clone()))
+            .collect::<HashMap<_, _>>();
+        if !changed.is_empty() {
+            self.db.update_ratings_table(&changed)?;
+        }
+
+        // Map them onto the stored movies now, rather than on the next scan
+        self.ratings = ratings;
+        let mapped = self.map_ratings();
+        self.save()?;
+        Ok(RatingsReport {
+            changed: changed.len(),
+            mapped,
+        })
+    }
+}
+
+// ==================
+// TV RELATED
+// ==================
+impl Library {
+    /// Same as `scan`, but for a `Show (Year)/Season 01/S01E01 - Title.mkv` layout
+    pub fn scan_tv(&mut self, mut on_event: impl FnMut(ScanEvent)) -> Result<ScanReport> {
+        let mut logger = Logger::new();
+        let mut legacy = self.episodes.keys().cloned().collect::<HashSet<u32>>();
+        let mut additions = HashMap::new();
+
+        let path_list = self
+            ._walk(3, &self.root)?
+            .filter(|p| p.depth() >= 2)
+            .filter(|p| p.path().extension().is_some_and(|ext| ext == "mkv"))
+            .map(|e| e.into_path())
+            .collect::<Vec<_>>();
+
+        let total = path_list.len();
+        for (done, path) in path_list.iter().enumerate() {
+            on_event(ScanEvent::Progress { done, total });
+            let hash = match Movie::read_metadata(path) {
+                Ok((_, hash)) => hash,
+                Err(e) => {
+                    on_event(ScanEvent::Skipped(&e));
+                    continue;
+                }
+            };
+            if !legacy.remove(&hash) {
+                let show_dir = path
+                    .strip_prefix(&self.root)
+                    .ok()
+                    .and_then(|rel| rel.components().next())
+                    .map(|show| self.root.join(show));
+
+                match show_dir.map(|dir| Episode::new(path, &dir, self.config.scan.read_budget())) {
+                    Some(Ok(ep)) => {
+                        logger.new.insert(ep.display_name());
+                        additions.insert(hash, ep);
+                    }
+                    Some(Err(e)) => on_event(ScanEvent::Skipped(&e)),
+                    None => {}
+                }
+            }
+        }
+        on_event(ScanEvent::Progress { done: total, total });
+
+        for leftover in &legacy {
+            if let Some(ep) = self.episodes.remove(leftover) {
+                let name = ep.display_name();
+                match logger.new.remove(&name) {
+                    true => logger.updated.insert(name),
+                    false => logger.removed.insert(name),
+                };
+            }
+        }
 
         if !logger.is_empty() {
-            self.db
-                .update_movie_table(&self.collection, &self.legacy_collection)
-                .unwrap_or_else(|e| println!("Failed to update the database.\nError: {e}"));
-            logger.output();
+            self.db.update_episode_table(&additions, &legacy)?;
         }
+        self.episodes.extend(additions);
+        Ok(logger.report(0, 0))
     }
 
-    /// Given a `user_name` (String) from letterboxd, scrape ratings and store in database
-    pub fn update_ratings(&mut self, user_name: &impl AsRef<str>) -> Result<()> {
-        let ratings = Self::retrieve_ratings(user_name.as_ref());
+    /// Per season codecs, resolutions, size and missing episode numbers
+    fn season_report(&self) -> PolarsResult<DataFrame> {
+        let reports = SeasonReport::build(self.episodes.values());
+        let column = |f: fn(&SeasonReport) -> String| reports.iter().map(f).collect::<Vec<_>>();
 
-        match self.db.update_ratings_table(&ratings) {
-            Ok(_) => {
-                println!("ADDED {} RATINGS!", ratings.len());
-                self.ratings = ratings;
+        DataFrame::new(vec![
+            Series::new("Show", column(|r| r.show.clone())),
+            Series::new("Season", column(|r| format!("{:02}", r.season))),
+            Series::new("Eps", column(|r| r.episodes.to_string())),
+            Series::new("Missing", column(|r| r.missing_str())),
+            Series::new("Codec", column(|r| r.codecs.clone())),
+            Series::new("Res", column(|r| r.resolutions.clone())),
+            Series::new("Size", column(|r| format!("{:.2} GB", r.size))),
+        ])
+    }
+}
+
+// ==================
+// WATCH MODE
+// ==================
+impl Library {
+    /// Watch root for file system events and rescan only the movie folders they touch,
+    /// once nothing in them has changed for `watch.debounce_secs`. Runs until interrupted.
+    /// What each rescan changed, and files it could not read, are passed to `on_event`.
+    pub fn watch(&mut self, mut on_event: impl FnMut(ScanEvent)) -> Result<()> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx).map_err(Error::watch(&self.root))?;
+        watcher
+            .watch(&self.root, RecursiveMode::Recursive)
+            .map_err(Error::watch(&self.root))?;
+
+        let quiet = Duration::from_secs(self.config.watch.debounce_secs);
+        let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+        let depth = self.config.scan.max_depth + 1;
+        self.exclusions = Some(Exclusions::load(&self.root, &self.config.scan.exclude, depth)?);
+        loop {
+            match rx.recv_timeout(Duration::from_secs(1)) {
+                Ok(Ok(event)) if !event.kind.is_access() => {
+                    // A broken `.numovignore` keeps the rules from before it changed
+                    if event.paths.iter().any(|p| p.file_name().is_some_and(|n| n == IGNORE_FILE)) {
+                        match Exclusions::load(&self.root, &self.config.scan.exclude, depth) {
+                            Ok(exclusions) => self.exclusions = Some(exclusions),
+                            Err(e) => on_event(ScanEvent::Warning(&e)),
+                        }
+                    }
+                    for dir in event.paths.iter().filter_map(|p| self.affected_dir(p)) {
+                        pending.insert(dir, Instant::now());
+                    }
+                }
+                Ok(Ok(_)) | Err(RecvTimeoutError::Timeout) => {}
+                Ok(Err(e)) => on_event(ScanEvent::Warning(&Error::watch(&self.root)(e))),
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.exclusions = None;
+                    return Ok(());
+                }
             }
-            Err(e) => println!("Could not scrape ratings!\nError: {e}"),
+
+            let ready = pending
+                .iter()
+                .filter(|(_, changed)| changed.elapsed() >= quiet)
+                .map(|(dir, _)| dir.clone())
+                .collect::<Vec<_>>();
+            self.store_checksums(&mut on_event)?;
+            if ready.is_empty() {
+                continue;
+            }
+            for dir in &ready {
+                pending.remove(dir);
+            }
+
+            // A folder inside another ready folder is rescanned along with it
+            let dirs = ready
+                .iter()
+                .filter(|dir| !ready.iter().any(|other| other != *dir && dir.starts_with(other)))
+                .collect::<Vec<_>>();
+            self.rescan(&dirs, &mut on_event)?;
+        }
+    }
+
+    /// The folder to rescan for a changed path: the movie folder of a file,
+    /// or the folder itself (a movie or collection folder)
+    fn affected_dir(&self, path: &Path) -> Option<PathBuf> {
+        path.strip_prefix(&self.root).ok()?;
+        let is_file = match path.exists() {
+            true => path.is_file(),
+            // Gone already, so guess by extension
+            false => path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| ["mkv", "srt", "ass", "ssa", "sup", "idx", "nfo"].contains(&e)),
         };
+        match is_file {
+            true => Some(Extra::movie_dir(path)),
+            false if ExtraKind::from_folder(&path.file_name()?.to_string_lossy()).is_some() => {
+                Some(path.parent()?.to_path_buf())
+            }
+            false => Some(path.to_path_buf()),
+        }
+    }
+
+    /// Rescan the movie folders below `dirs` and write only what changed
+    fn rescan(&mut self, dirs: &[&PathBuf], on_event: &mut dyn FnMut(ScanEvent)) -> Result<()> {
+        let mut logger = Logger::new();
+        let mut legacy = HashSet::new();
+        let mut legacy_extras = HashSet::new();
+
+        let old_dirs = self
+            .folders
+            .keys()
+            .filter(|folder| dirs.iter().any(|dir| folder.starts_with(dir)))
+            .cloned()
+            .collect::<Vec<_>>();
+        for hash in old_dirs.iter().flat_map(|dir| self.folders.remove(dir).unwrap_or_default()) {
+            match self.collection.contains_key(&hash) {
+                true => legacy.insert(hash),
+                false => legacy_extras.insert(hash),
+            };
+        }
+
+        for dir in dirs {
+            let path_list = self._get_dirs_under(dir)?;
+            for (movie_dir, paths) in Self::group_by_movie_dir(&path_list) {
+                self.scan_movie_dir(movie_dir, &paths, &mut legacy, &mut legacy_extras, &mut logger, on_event);
+            }
+        }
+
+        self.drop_leftovers(&legacy, &legacy_extras, &mut logger);
+        let (extras_saved, extras_removed) = self.save()?;
+        let report = logger.report(extras_saved, extras_removed);
+        if !report.is_empty() {
+            on_event(ScanEvent::Rescanned(&report));
+        }
         Ok(())
     }
 }
 
+// ==================
+// CHECKSUMS
+// ==================
+impl Library {
+    /// Queue a file for background hashing when checksums are on and there is
+    /// none for its current size and mtime
+    fn queue_checksum(&mut self, path: &Path) {
+        if !self.config.checksum.enabled {
+            return;
+        }
+        if Movie::read_metadata(path).is_ok_and(|(_, hash)| self.checksums.get(path) == Some(&hash)) {
+            return;
+        }
+        let rate = self.config.checksum.max_mb_per_sec * 1024 * 1024;
+        self.hasher
+            .get_or_insert_with(|| Hasher::start(rate))
+            .queue(path.to_path_buf());
+    }
+
+    /// Store the checksums the background hasher has finished, without waiting for the rest.
+    /// Files that could not be hashed are passed to `on_event`.
+    pub fn store_checksums(&mut self, on_event: &mut dyn FnMut(ScanEvent)) -> Result<()> {
+        let Some(hasher) = &mut self.hasher else {
+            return Ok(());
+        };
+        let mut done = Vec::new();
+        for (_, checksum) in hasher.results(Duration::ZERO) {
+            match checksum {
+                Ok(checksum) => done.push(checksum),
+                Err(e) => on_event(ScanEvent::Skipped(&e)),
+            }
+        }
+        if !done.is_empty() {
+            self.db.update_checksums_table(&done)?;
+            self.checksums.extend(done.into_iter().map(|c| (c.path, c.file_hash)));
+        }
+        Ok(())
+    }
+
+    /// Store the finished checksums before exiting. Files still queued are
+    /// hashed by the next scan that finds them, or by `fsck`. Returns how many are left.
+    pub fn finish_checksums(&mut self, mut on_event: impl FnMut(ScanEvent)) -> Result<usize> {
+        self.store_checksums(&mut on_event)?;
+        Ok(self.hasher.as_ref().map_or(0, Hasher::pending))
+    }
+
+    /// Hash every file under the root again whose size and mtime have not changed
+    /// since its checksum was stored, and report those whose content has. Files
+    /// without a checksum for their current size and mtime, such as those a scan
+    /// left queued, get their first one.
+    pub fn fsck(&mut self, mut on_event: impl FnMut(ScanEvent)) -> Result<FsckReport> {
+        let stored = self.db.fetch_checksums()?;
+        let paths = self._get_dirs()?;
+        let files = paths
+            .iter()
+            .map(|path| match stored.get(path) {
+                Some((file_hash, sum)) if Movie::read_metadata(path).is_ok_and(|(_, h)| h == *file_hash) => {
+                    (path, Some(sum))
+                }
+                _ => (path, None),
+            })
+            .collect::<Vec<_>>();
+
+        let total = files.len();
+        let mut hashed = Vec::new();
+        let mut changed = Vec::new();
+        for (done, (path, sum)) in files.iter().enumerate() {
+            on_event(ScanEvent::Progress { done, total });
+            match (checksum::compute(path, 0), sum) {
+                (Ok(checksum), Some(sum)) if checksum.hex() != **sum => {
+                    changed.push(path.strip_prefix(&self.root).unwrap_or(path).display().to_string())
+                }
+                (Ok(checksum), _) => hashed.push(checksum),
+                (Err(e), _) => on_event(ScanEvent::Skipped(&e)),
+            }
+        }
+        on_event(ScanEvent::Progress { done: total, total });
+        self.db.update_checksums_table(&hashed)?;
+        self.checksums.extend(hashed.into_iter().map(|c| (c.path, c.file_hash)));
+
+        let checked = files.iter().filter(|(_, sum)| sum.is_some()).count();
+        Ok(FsckReport {
+            changed,
+            checked,
+            first: total - checked,
+        })
+    }
+}
+
 // ==================
 // RATINGS RELATED
 // ==================
 impl Library {
-    fn retrieve_ratings(user_name: impl AsRef<str>) -> HashMap<String, String> {
+    fn retrieve_ratings(
+        user_name: impl AsRef<str>,
+        on_event: &mut dyn FnMut(ScanEvent),
+    ) -> Result<HashMap<String, String>> {
         let url = format! {"https://letterboxd.com/{}/films/", user_name.as_ref()};
         let mut catalogue = HashMap::new();
 
-        let get_doc = |url: &str| -> Document {
-            Document::from(
-                ureq::get(url)
-                    .call()
-                    .expect("Failed to make request.")
-                    .into_string()
-                    .expect("Failed to convert response into string.")
-                    .as_str(),
-            )
+        let get_doc = |url: &str| -> Result<Document> {
+            let http = |source| Error::Http {
+                url: url.to_string(),
+                source,
+            };
+            let body = ureq::get(url)
+                .call()
+                .map_err(|e| http(Box::new(e)))?
+                .into_string()
+                .map_err(|e| http(Box::new(e)))?;
+            Ok(Document::from(body.as_str()))
         };
 
-        let doc = get_doc(&url);
+        let doc = get_doc(&url)?;
 
         let mut last_page = match doc.find(Class("paginate-pages")).into_selection().first() {
             Some(n) => n
                 .text()
                 .split_whitespace()
                 .last()
-                .unwrap()
-                .parse::<usize>()
-                .unwrap(),
+                .and_then(|page| page.parse::<usize>().ok())
+                .ok_or_else(|| Error::Html {
+                    url: url.clone(),
+                    reason: format!("no page count in {:?}", n.text()),
+                })?,
             None => 1,
         };
 
-        Self::extract_info(&doc, &mut catalogue);
+        Self::extract_info(&doc, &url, &mut catalogue)?;
 
-        let mut lb_prog = Prog::new(last_page, "scraping user ratings");
-        lb_prog.pb.show_counter = false;
+        let total = last_page;
+        on_event(ScanEvent::Progress { done: 1, total });
         while last_page > 1 {
-            let doc = get_doc(&format!("{}/page/{}", url, last_page));
-            Self::extract_info(&doc, &mut catalogue);
+            let page_url = format!("{}/page/{}", url, last_page);
+            Self::extract_info(&get_doc(&page_url)?, &page_url, &mut catalogue)?;
             last_page -= 1;
-            lb_prog.inc();
+            on_event(ScanEvent::Progress { done: total - last_page + 1, total });
         }
-        lb_prog.end();
-        catalogue
+        Ok(catalogue)
     }
 
-    fn extract_info(doc: &Document, catalogue: &mut HashMap<String, String>) {
+    fn extract_info(doc: &Document, url: &str, catalogue: &mut HashMap<String, String>) -> Result<()> {
         for poster in doc.find(Class("poster-container")) {
             let title = poster
                 .find(Attr("alt", ()))
                 .into_selection()
                 .first()
-                .unwrap()
-                .attr("alt")
-                .expect("Could not find alt tag.")
+                .and_then(|n| n.attr("alt"))
+                .ok_or_else(|| Error::Html {
+                    url: url.to_string(),
+                    reason: "a poster has no alt text".to_string(),
+                })?
                 .replace(':', "-");
 
             let rating = poster.text().trim().to_string();
@@ -178,6 +980,7 @@ impl Library {
             }
             catalogue.insert(title, rating);
         }
+        Ok(())
     }
 }
 
@@ -185,83 +988,363 @@ impl Library {
 // External Functionality
 // =========================
 impl Library {
-    /// Builds a csv file, with each row representing a movie
+    /// Builds a csv file at `path`, with each row representing a movie
     /// and each column representing an aspect.
-    /// Outputs to directory program was run from
-    pub fn output_to_csv(&self) {
-        let output_str = "Title,Year,Rating,Duration,Size,Resolution,V_Codec,Bit_depth,A_Codec,Channels,Sub_Format,Hash,Audio #,Sub #\n".to_string()
+    pub fn output_to_csv(&self, path: &Path) -> Result<()> {
+        let output_str = "Title,Year,Rating,Duration,Size,Resolution,V_Codec,Bit_depth,A_Codec,Channels,Sub_Format,Hash,Audio #,Sub #,Source,Group,Pattern,Genres,HDR,Edition,Parts,Collection\n".to_string()
                 + self._get_lib_str().as_str();
 
-        match std::fs::write("m_log.csv", output_str) {
-            Ok(_) => println!("Successfully wrote data to ./m_log.csv"),
-            Err(e) => eprintln!("{e}"),
-        }
+        std::fs::write(path, output_str).map_err(Error::io(path))
     }
-    /// Renames folders based on format determined in get_new_name()
-    pub fn rename_folders(&mut self) {
-        let mut old_hashes = HashSet::new();
 
-        for path in &Self::_get_dirs(&self.root) {
-            let hash = Movie::read_metadata(path).1;
 
-            if let Some(mov) = self.collection.get(&hash) {
-                let old_name = path.parent().unwrap();
-                let new_name = self.get_new_name(mov);
+    /// Writes a `movie.nfo` into each movie folder found under root whose movie
+    /// is in the database, or a `<file name>.nfo` next to each movie of a folder
+    /// that holds several. With `dry_run`, only reports what would be written.
+    pub fn export_nfo(&self, policy: Overwrite, dry_run: bool) -> Result<NfoExport> {
+        let mut export = NfoExport::default();
+        let units = self._get_units()?;
+        let mut films_in = HashMap::new();
+        let movies = units.iter().filter(|(hash, _)| self.collection.contains_key(hash));
+        for dir in movies.filter_map(|(_, unit)| unit[0].parent()) {
+            *films_in.entry(dir).or_insert(0) += 1;
+        }
+
+        for (hash, unit) in &units {
+            if let (Some(mov), Some(dir)) = (self.collection.get(hash), unit[0].parent()) {
+                let nfo_path = match films_in[dir] {
+                    1 => dir.join("movie.nfo"),
+                    _ => unit[0].with_extension("nfo"),
+                };
+
+                if !Nfo::may_write(&nfo_path, policy) {
+                    export.kept += 1;
+                    continue;
+                }
+
+                if !dry_run {
+                    std::fs::write(&nfo_path, Nfo::render(mov)).map_err(Error::io(&nfo_path))?;
+                }
+                export.written.push(nfo_path);
+            }
+        }
+        Ok(export)
+    }
+
+    /// Layout problems under root, such as badly named folders and stray files
+    pub fn lint(&self) -> Result<Vec<Issue>> {
+        let max_depth = self.config.scan.max_depth;
+        let entries = self._walk(max_depth + 1, &self.root)?.collect();
+        Ok(lint::check(&self.root, entries, max_depth, &self.config.patterns))
+    }
 
-                if new_name != old_name {
-                    old_hashes.insert(hash);
-                    let mut m = self.collection.remove(&hash).unwrap();
+    /// Check the files of every movie under the root for damage and store the results
+    pub fn verify(&mut self, mut on_event: impl FnMut(ScanEvent)) -> Result<VerifyReport> {
+        // Files not scanned yet are checked too, but only scanned movies keep their result
+        let units = self
+            ._get_units()?
+            .into_iter()
+            .filter(|(hash, _)| !self.extras.contains_key(hash))
+            .collect::<Vec<_>>();
 
-                    std::fs::rename(old_name, &new_name).unwrap_or_else(|e| {
-                        println!("Error writing to {:?}\nError: {e}", &new_name)
-                    });
+        let total = units.len();
+        let (mut results, mut unscanned) = (Vec::new(), Vec::new());
+        for (done, (hash, unit)) in units.iter().enumerate() {
+            on_event(ScanEvent::Progress { done, total });
+            let mut problems = Vec::new();
+            for path in unit {
+                let found = verify::check(path);
+                match unit.len() {
+                    1 => problems.extend(found),
+                    _ => {
+                        let file = path.file_name().unwrap_or_default().to_string_lossy();
+                        problems.extend(found.into_iter().map(|p| format!("{file}: {p}")));
+                    }
+                }
+            }
+            match self.collection.get(hash) {
+                Some(movie) => results.push(Verification::new(*hash, movie.display_name(), problems)),
+                None => {
+                    let path = unit[0].strip_prefix(&self.root).unwrap_or(&unit[0]);
+                    unscanned.push(Verification::new(*hash, path.display().to_string(), problems));
+                }
+            }
+        }
+        on_event(ScanEvent::Progress { done: total, total });
+        self.db.update_verifications_table(&results)?;
+        Ok(VerifyReport { results, unscanned })
+    }
 
-                    let file_name = path.file_name().unwrap();
-                    let new_path = &new_name.join(file_name);
-                    let new_hash = Movie::read_metadata(new_path).1;
-                    m.hash = new_hash;
-                    self.collection.insert(new_hash, m);
+    /// Compare the films under two roots by content and by title, year and edition, without
+    /// touching the database
+    pub fn compare(&mut self, a: PathBuf, b: PathBuf, mut on_event: impl FnMut(ScanEvent)) -> Result<Comparison> {
+        let a = a.canonicalize().map_err(Error::io(&a))?;
+        let b = b.canonicalize().map_err(Error::io(&b))?;
+        let root = std::mem::replace(&mut self.root, a.clone());
+        let units_a = self._get_units();
+        self.root = b.clone();
+        let units_b = self._get_units();
+        self.root = root;
+        let (units_a, units_b) = (units_a?, units_b?);
+        let checksums = self.db.fetch_checksums()?;
 
-                    println!(
-                        "\n\t\t{}\n\t\t==>\t{}",
-                        old_name.file_name().unwrap().to_string_lossy(),
-                        new_name.file_name().unwrap().to_string_lossy(),
-                    )
+        // Films the library does not know yet are read, but only kept for the comparison,
+        // and nothing is written to their files
+        let mut scanned = HashMap::new();
+        for (hash, unit) in units_a.iter().chain(&units_b) {
+            if self.collection.contains_key(hash) || self.extras.contains_key(hash) {
+                continue;
+            }
+            let parts = unit
+                .iter()
+                .filter_map(|path| Some((path, probe::open(path, self.config.scan.read_budget()).ok()?.matroska)))
+                .collect::<Vec<_>>();
+            let secs = parts.iter().map(|(_, mkv)| mkv.info.duration.unwrap_or_default().as_secs()).sum::<u64>();
+            if !parts.is_empty() && (parts.len() > 1 || secs >= self.config.extras.max_minutes * 60) {
+                match Movie::collect_parts(&parts, *hash, &self.config, false, &mut on_event) {
+                    Ok(movie) => _ = scanned.insert(*hash, movie),
+                    Err(e) => on_event(ScanEvent::Skipped(&e)),
                 }
             }
         }
 
-        if !old_hashes.is_empty() {
-            println!("\nRenamed {} paths!", old_hashes.len());
+        let copies = |root: &Path, units: &[(u32, Vec<PathBuf>)]| {
+            units
+                .iter()
+                .filter_map(|(hash, unit)| {
+                    let movie = self.collection.get(hash).or_else(|| scanned.get(hash))?;
+                    let dir = unit[0].parent()?;
+                    let checksum = unit
+                        .iter()
+                        .map(|p| match checksums.get(p) {
+                            Some((file_hash, sum)) if Movie::read_metadata(p).is_ok_and(|(_, h)| h == *file_hash) => {
+                                Some(sum.as_str())
+                            }
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<_>>>();
+                    Some(compare::FilmCopy {
+                        movie,
+                        path: dir.strip_prefix(root).unwrap_or(dir).display().to_string(),
+                        bytes: unit.iter().map(|p| Movie::read_metadata(p).map_or(0, |(bytes, _)| bytes)).sum(),
+                        checksum: checksum.map(|sums| sums.join(",")),
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+        let (identical, differences) = compare::diff(copies(&a, &units_a), copies(&b, &units_b));
+        Ok(Comparison { identical, differences })
+    }
 
-            self.db
-                .update_movie_table(&self.collection, &old_hashes)
-                .unwrap_or_else(|e| println!("Failed to update the database.\nError: {e}"));
+    /// Plan which films go on which backup drive (capacities in GB). Films placed by
+    /// an earlier plan stay where they are, whatever their files are renamed to, and
+    /// new versions of them join them; only films new since then are placed.
+    /// Without capacities, the drives of the stored plan are used; `None` if there are none.
+    /// With `dry_run`, the plan is not stored.
+    pub fn plan_backup(&mut self, capacities: &[f32], by_rating: bool, dry_run: bool) -> Result<Option<BackupPlan>> {
+        let (stored, mut plan) = self.db.fetch_backup_plan()?;
+        let capacities = match capacities.is_empty() {
+            true => stored,
+            false => capacities.to_vec(),
+        };
+        if capacities.is_empty() {
+            return Ok(None);
         }
+
+        // Forget films that are gone, or whose drive was dropped from the list
+        let works = self.collection.values().map(Work::key).collect::<HashSet<_>>();
+        let removed = plan
+            .iter()
+            .filter(|(work, drive)| !works.contains(*work) || **drive >= capacities.len())
+            .map(|(work, _)| work.clone())
+            .collect::<HashSet<_>>();
+        plan.retain(|work, _| !removed.contains(work));
+
+        let mut drives = capacities
+            .iter()
+            .map(|capacity| backup::Drive { capacity: *capacity, used: 0.0 })
+            .collect::<Vec<_>>();
+        let mut homes = HashMap::new();
+        let (mut new, mut planned) = (Vec::new(), Vec::new());
+        for movie in self.collection.values() {
+            match plan.get(&Work::key(movie)) {
+                Some(drive) => planned.push((movie, *drive)),
+                None => new.push(movie),
+            }
+        }
+
+        // Versions of planned works join them while their drive has room for them
+        planned.sort_by_key(|(m, _)| (m.display_name(), m.hash));
+        let mut kept = Vec::new();
+        let mut overflow = Vec::new();
+        for (movie, drive) in planned {
+            if drives[drive].free() < movie.size {
+                overflow.push((movie, drive));
+                continue;
+            }
+            drives[drive].used += movie.size;
+            if let Some(c) = &movie.collection {
+                homes.entry(c.clone()).or_insert(drive);
+            }
+            kept.push((movie, drive));
+        }
+
+        let (mut placed, unplaced) = backup::place(&mut drives, homes, new, by_rating);
+        placed.sort_by_key(|(m, drive)| (*drive, m.display_name()));
+        let placed_works = placed.iter().map(|(m, drive)| (Work::key(m), *drive)).collect::<HashMap<_, _>>();
+
+        let on = |films: &[(&Movie, usize)], d: usize| films.iter().filter(|(_, drive)| *drive == d).count() as u32;
+        let report = BackupPlan {
+            drives: drives
+                .iter()
+                .enumerate()
+                .map(|(d, drive)| DriveUse {
+                    capacity: drive.capacity,
+                    used: drive.used,
+                    films: on(&kept, d) + on(&placed, d),
+                    new: on(&placed, d),
+                })
+                .collect(),
+            placed: placed.iter().map(|(m, drive)| PlannedFilm::new(m, Some(*drive))).collect(),
+            overflow: overflow.iter().map(|(m, drive)| PlannedFilm::new(m, Some(*drive))).collect(),
+            unplaced: unplaced.iter().map(|m| PlannedFilm::new(m, None)).collect(),
+        };
+
+        if !dry_run {
+            let additions = placed_works.into_iter().collect::<Vec<_>>();
+            self.db.update_backup_plan(&capacities, &additions, &removed)?;
+        }
+        Ok(Some(report))
+    }
+
+    /// Read every MKV under the root with both the header-only probe and
+    /// `Matroska::open`, and compare bytes read and time taken
+    pub fn bench_probe(&self, mut on_event: impl FnMut(ScanEvent)) -> Result<Vec<ProbeBench>> {
+        let budget = self.config.scan.read_budget();
+        let mut benches = Vec::new();
+        for path in self._get_dirs()? {
+            match probe::compare(&path, budget) {
+                Ok([(probe_bytes, probe_time), (open_bytes, open_time)]) => benches.push(ProbeBench {
+                    file: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+                    probe_bytes,
+                    probe_time,
+                    open_bytes,
+                    open_time,
+                }),
+                Err(e) => on_event(ScanEvent::Skipped(&e)),
+            }
+        }
+        Ok(benches)
+    }
+
+    /// Renames folders based on format determined in get_new_name(). Stops at the
+    /// first folder that can not be renamed, keeping the renames done until then.
+    /// Returns the number of folders renamed.
+    pub fn rename_folders(&mut self, mut on_event: impl FnMut(ScanEvent)) -> Result<usize> {
+        let mut renamed = 0;
+        let mut failure = None;
+
+        for (hash, unit) in self._get_units()? {
+            let (Some(mov), Some(old_name)) = (self.collection.get(&hash), unit[0].parent()) else {
+                continue;
+            };
+            let new_name = self.get_new_name(mov, old_name.parent().unwrap_or(&self.root));
+            if new_name == old_name {
+                continue;
+            }
+
+            let moved = new_name
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| std::fs::rename(old_name, &new_name))
+                .map_err(Error::io(&new_name));
+            let new_paths = unit
+                .iter()
+                .map(|path| new_name.join(path.file_name().unwrap_or_default()))
+                .collect::<Vec<_>>();
+            let new_hash = match moved.and_then(|_| stack::hash(&new_paths.iter().collect::<Vec<_>>())) {
+                Ok(new_hash) => new_hash,
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            };
+
+            renamed += 1;
+            on_event(ScanEvent::Renamed { from: old_name, to: &new_name });
+            if let Some(mut m) = self.collection.remove(&hash) {
+                m.hash = new_hash;
+                m.collection = self.collection_of(&new_paths[0]);
+                self.collection.insert(new_hash, m);
+                self.dirty.removed.insert(hash);
+                self.dirty.movies.insert(new_hash);
+            }
+        }
+
+        if renamed > 0 {
+            self.save()?;
+        }
+        failure.map_or(Ok(renamed), Err)
     }
 
-    /// Creates a new path name for file
-    fn get_new_name(&self, m: &Movie) -> PathBuf {
-        let new_path = format!(
-            "{} ({}) [{} {} {} {:?}-{}] ({:.2} GB)",
-            m.title,
-            m.year,
-            m.video.resolution,
-            m.video.codec,
-            m.video.bit_depth,
-            m.audio.codec,
-            match m.audio.channels {
-                ch if ch < 1.5 => "mono".to_string(),
-                ch if ch < 2.5 => "stereo".to_string(),
-                x => format!("{}", &x),
-            },
-            m.size
-        );
-        PathBuf::from(&self.root).join(new_path)
+    /// Creates the new path of a movie folder from the `rename.format` template.
+    /// Tokens: `{title}` `{year}` `{edition}` `{collection}` `{res}` `{codec}` `{bits}`
+    /// `{audio}` `{channels}` `{size}` `{source}` `{group}`; `{edition}` expands to
+    /// ` {edition-...}` or nothing. A `/` in the template makes nested folders, and
+    /// the movie stays in its current parent folder unless `{collection}` is used.
+    fn get_new_name(&self, m: &Movie, parent: &Path) -> PathBuf {
+        let format = &self.config.rename.format;
+        let channels = match m.audio.channels {
+            ch if ch < 1.5 => "mono".to_string(),
+            ch if ch < 2.5 => "stereo".to_string(),
+            x => format!("{}", &x),
+        };
+        let render = |segment: &str| {
+            segment
+                .replace("{title}", &m.title.replace(':', "-"))
+                .replace("{year}", &m.year.to_string())
+                .replace("{edition}", &m.edition.as_ref().map(|e| format!(" {{edition-{e}}}")).unwrap_or_default())
+                .replace("{collection}", m.collection.as_deref().unwrap_or_default())
+                .replace("{res}", &m.video.resolution.to_string())
+                .replace("{codec}", &m.video.codec.to_string())
+                .replace("{bits}", &m.video.bit_depth.to_string())
+                .replace("{audio}", &format!("{:?}", m.audio.codec))
+                .replace("{channels}", &channels)
+                .replace("{size}", &format!("{:.2}", m.size))
+                .replace("{source}", &m.source.as_ref().map(|s| s.to_string()).unwrap_or_default())
+                .replace("{group}", m.group.as_deref().unwrap_or_default())
+        };
+
+        let base = match format.contains("{collection}") {
+            true => self.root.clone(),
+            false => parent.to_path_buf(),
+        };
+        // Split before rendering, so a `/` in a title can not create a folder
+        format
+            .split('/')
+            .flat_map(|segment| match segment.trim() {
+                "{collection}" => m.collection.iter().flat_map(|c| c.split('/')).map(str::to_string).collect(),
+                segment => vec![render(segment).replace('/', "-")],
+            })
+            .map(|segment| segment.trim().to_string())
+            .filter(|segment| !segment.is_empty())
+            .fold(base, |path, segment| path.join(segment))
     }
 
-    pub fn handle_dataframe(&self, input: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let output_str = "Title,Year,Stars,Dur,Size,Res,Vodec,Bits,Codec,Ch,Fmt,Hash,A#,S#\n"
+    /// Folders between the root and a movie's folder, joined with `/`
+    fn collection_of(&self, path: &Path) -> Option<String> {
+        let movie_dir = Extra::movie_dir(path);
+        let between = movie_dir.parent()?.strip_prefix(&self.root).ok()?;
+        let names = between
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>();
+        (!names.is_empty()).then(|| names.join("/"))
+    }
+
+    /// The `stats` table named `input`
+    pub fn stats(&self, input: &str) -> Result<DataFrame> {
+        let output_str = "Title,Year,Stars,Dur,Size,Res,Vodec,Bits,Codec,Ch,Fmt,Hash,A#,S#,Src,Grp,Pat,Genres,HDR,Edition,Parts,Collection\n"
             .to_string()
             + self._get_lib_str().as_str();
 
@@ -273,17 +1356,12 @@ impl Library {
             .finish()?;
 
         let mut df = match input {
-            "full" => {
-                env::set_var("POLARS_FMT_STR_LEN", "30");
-                env::set_var("POLARS_FMT_MAX_COLS", "10");
-                env::set_var("POLARS_FMT_MAX_ROWS", "-1");
-                raw_df
-                    .select([
-                        "Year", "Title", "Stars", "Dur", "Size", "Res", "Bits", "Codec", "Ch",
-                        "Fmt",
-                    ])?
-                    .sort(["Title"], false, false)?
-            }
+            "full" => raw_df
+                .select([
+                    "Year", "Title", "Edition", "Stars", "Dur", "Size", "Res", "Bits",
+                    "Codec", "Ch", "Fmt",
+                ])?
+                .sort(["Title"], false, false)?,
             "audio" => raw_df
                 .select(["A#", "Title", "Stars", "Codec", "Ch"])?
                 .sort(["A#", "Title"], vec![true, false], false)?,
@@ -297,25 +1375,208 @@ impl Library {
                 vec![true, false],
                 false,
             )?,
+            "patterns" => raw_df.select(["Pat", "Title", "Year"])?.sort(
+                ["Pat", "Title"],
+                vec![false, false],
+                false,
+            )?,
+            "collections" => self.collection_counts()?,
+            "editions" => self.edition_groups()?,
+            "extras" => self.extras_report()?,
+            "seasons" => self.season_report()?,
+            "sidecars" => self.sidecar_report()?,
+            "verified" => self.verification_report()?,
+            "versions" => self.version_ranks()?,
+            "genres" => self.genre_counts()?,
             "year" => raw_df
                 .select(["Title", "Year"])?
                 .sort(["Year"], false, false)?,
             _ => raw_df,
         };
 
-        if input != "full" {
-            df = df.slice(0, DATAFRAME_LEN);
+        if input != "full" && input != "seasons" {
+            df = df.slice(0, self.config.tables.stats_rows);
+        }
+        Ok(df)
+    }
+
+    /// Films with editions or several files, grouped by title and year
+    fn edition_groups(&self) -> PolarsResult<DataFrame> {
+        let mut groups: HashMap<(String, i16), Vec<&Movie>> = HashMap::new();
+        for movie in self.collection.values() {
+            groups
+                .entry((movie.title.to_lowercase(), movie.year))
+                .or_default()
+                .push(movie);
         }
 
-        println!("{:?}", df);
+        let (mut titles, mut years, mut counts, mut editions) = (vec![], vec![], vec![], vec![]);
+        for movies in groups.values() {
+            if movies.len() < 2 && movies[0].edition.is_none() {
+                continue;
+            }
+            let mut names = movies
+                .iter()
+                .map(|m| m.edition.clone().unwrap_or_else(|| "-".to_string()))
+                .collect::<Vec<_>>();
+            names.sort();
 
-        Ok(())
+            titles.push(movies[0].title.clone());
+            years.push(movies[0].year as i32);
+            counts.push(movies.len() as u32);
+            editions.push(names.join(", "));
+        }
+
+        DataFrame::new(vec![
+            Series::new("Title", titles),
+            Series::new("Year", years),
+            Series::new("#", counts),
+            Series::new("Editions", editions),
+        ])?
+        .sort(["Title"], false, false)
     }
 
-    fn _get_lib_str(&self) -> String {
-        let mut str_vec = self
+    /// Works with more than one version, ranked by quality
+    fn version_ranks(&self) -> PolarsResult<DataFrame> {
+        let works = Work::group(self.collection.values());
+        let rows = works
+            .iter()
+            .filter(|work| work.versions.len() > 1)
+            .flat_map(|work| work.versions.iter().enumerate().map(move |(i, m)| (work, i, m)))
+            .collect::<Vec<_>>();
+
+        let column = |f: fn(&Work, usize, &Movie) -> String| {
+            rows.iter().map(|(w, i, m)| f(w, *i, m)).collect::<Vec<_>>()
+        };
+
+        DataFrame::new(vec![
+            Series::new(
+                "Work",
+                column(|w, _, _| match w.edition {
+                    Some(e) => format!("{} ({}) {{{e}}}", w.title, w.year),
+                    None => format!("{} ({})", w.title, w.year),
+                }),
+            ),
+            Series::new("Rank", column(|_, i, _| (i + 1).to_string())),
+            Series::new("Res", column(|_, _, m| m.video.resolution.to_string())),
+            Series::new("HDR", column(|_, _, m| m.video.hdr.to_string())),
+            Series::new(
+                "Src",
+                column(|_, _, m| m.source.as_ref().map(|s| s.to_string()).unwrap_or_default()),
+            ),
+            Series::new("Size", column(|_, _, m| format!("{:.2}", m.size))),
+            Series::new("Score", column(|_, _, m| m.quality_score().to_string())),
+        ])
+    }
+
+    /// Result of the last `verify` of each movie, damaged ones first
+    fn verification_report(&self) -> Result<DataFrame> {
+        let verifications = self.db.fetch_verifications()?;
+        let mut rows = self
             .collection
             .values()
+            .map(|m| {
+                let (status, problems, verified) = match verifications.get(&m.hash) {
+                    Some((problems, at)) if problems.is_empty() => ("OK", "", at.as_str()),
+                    Some((problems, at)) => ("Damaged", problems.as_str(), at.as_str()),
+                    None => ("Unverified", "", "-"),
+                };
+                (m.display_name(), status, verified, problems)
+            })
+            .collect::<Vec<_>>();
+        rows.sort_by_key(|(name, status, ..)| (*status == "OK", *status == "Unverified", name.clone()));
+
+        Ok(DataFrame::new(vec![
+            Series::new("Movie", rows.iter().map(|r| r.0.as_str()).collect::<Vec<_>>()),
+            Series::new("Status", rows.iter().map(|r| r.1).collect::<Vec<_>>()),
+            Series::new("Verified", rows.iter().map(|r| r.2).collect::<Vec<_>>()),
+            Series::new("Problems", rows.iter().map(|r| r.3).collect::<Vec<_>>()),
+        ])?)
+    }
+
+    /// Extras and the movie they belong to
+    fn extras_report(&self) -> PolarsResult<DataFrame> {
+        let mut extras = self.extras.values().collect::<Vec<_>>();
+        let movie_name = |e: &Extra| {
+            e.movie_hash
+                .and_then(|h| self.collection.get(&h))
+                .map_or("-".to_string(), |m| m.display_name())
+        };
+        extras.sort_by_key(|e| (movie_name(e), e.name.clone()));
+
+        DataFrame::new(vec![
+            Series::new("Movie", extras.iter().map(|e| movie_name(e)).collect::<Vec<_>>()),
+            Series::new("Kind", extras.iter().map(|e| e.kind.to_string()).collect::<Vec<_>>()),
+            Series::new("Name", extras.iter().map(|e| e.name.clone()).collect::<Vec<_>>()),
+            Series::new("Dur", extras.iter().map(|e| e.duration.clone()).collect::<Vec<_>>()),
+            Series::new("Size", extras.iter().map(|e| e.size).collect::<Vec<_>>()),
+        ])
+    }
+
+    /// External subtitle files, flagging those that repeat an embedded track
+    fn sidecar_report(&self) -> PolarsResult<DataFrame> {
+        let mut rows = self
+            .collection
+            .values()
+            .flat_map(|m| m.subs.tracks.iter().filter(|t| t.file.is_some()).map(move |t| (m, t)))
+            .collect::<Vec<_>>();
+        rows.sort_by_key(|(m, t)| (m.display_name(), t.file.clone()));
+
+        DataFrame::new(vec![
+            Series::new("Movie", rows.iter().map(|(m, _)| m.display_name()).collect::<Vec<_>>()),
+            Series::new("File", rows.iter().map(|(_, t)| t.file.clone().unwrap_or_default()).collect::<Vec<_>>()),
+            Series::new("Fmt", rows.iter().map(|(_, t)| t.format.to_string()).collect::<Vec<_>>()),
+            Series::new("Lang", rows.iter().map(|(_, t)| t.language.clone()).collect::<Vec<_>>()),
+            Series::new("Forced", rows.iter().map(|(_, t)| t.forced).collect::<Vec<_>>()),
+            Series::new(
+                "Dupe",
+                rows.iter()
+                    .map(|(m, t)| sidecar::is_duplicate(t, &m.subs.tracks))
+                    .collect::<Vec<_>>(),
+            ),
+        ])
+    }
+
+    /// Number of movies and total size per collection folder
+    fn collection_counts(&self) -> PolarsResult<DataFrame> {
+        let mut counts: BTreeMap<&str, (u32, f32)> = BTreeMap::new();
+        for movie in self.collection.values() {
+            let entry = counts.entry(movie.collection.as_deref().unwrap_or("-")).or_default();
+            entry.0 += 1;
+            entry.1 += movie.size;
+        }
+
+        DataFrame::new(vec![
+            Series::new("Collection", counts.keys().copied().collect::<Vec<_>>()),
+            Series::new("#", counts.values().map(|(n, _)| *n).collect::<Vec<_>>()),
+            Series::new("Size", counts.values().map(|(_, size)| *size).collect::<Vec<_>>()),
+        ])
+    }
+
+    /// Number of movies per genre
+    fn genre_counts(&self) -> PolarsResult<DataFrame> {
+        let mut counts: HashMap<&str, u32> = HashMap::new();
+        for genre in self.collection.values().flat_map(|m| &m.genres) {
+            *counts.entry(genre.as_str()).or_default() += 1;
+        }
+        let (genres, counts): (Vec<&str>, Vec<u32>) = counts.into_iter().unzip();
+
+        DataFrame::new(vec![Series::new("Genre", genres), Series::new("#", counts)])?.sort(
+            ["#", "Genre"],
+            vec![true, false],
+            false,
+        )
+    }
+
+    fn _get_lib_str(&self) -> String {
+        let query = Query {
+            genre: self.genre.clone(),
+            best_only: self.best_only,
+            ..Default::default()
+        };
+        let mut str_vec = self
+            .query(&query)
+            .into_iter()
             .map(|m| m.make_lines())
             .collect::<Vec<_>>();
 
@@ -328,68 +1589,112 @@ impl Library {
 // Private Stuff
 // =================
 impl Library {
-    /// Simple walk to find .mkv files provided a root (operates at a depth of 2 to follow a root/dir/file structure)
-    fn _get_dirs(root: &PathBuf) -> Vec<PathBuf> {
-        WalkDir::new(root)
-            .max_depth(2)
-            .into_iter()
-            .filter_map(|f| f.ok())
-            .filter(|p| p.path().extension().map_or(false, |ext| ext == "mkv"))
+    /// Simple walk to find .mkv files provided a root, down to `scan.max_depth` (2 follows a root/dir/file structure,
+    /// 3 allows root/collection/dir/file). Files in extras folders (root/dir/Featurettes/file) are included as well.
+    fn _get_dirs(&self) -> Result<Vec<PathBuf>> {
+        self._get_dirs_under(&self.root)
+    }
+
+    /// `_get_dirs`, limited to the files below `under`
+    fn _get_dirs_under(&self, under: &Path) -> Result<Vec<PathBuf>> {
+        let max_depth = self.config.scan.max_depth;
+        Ok(self
+            ._walk(max_depth + 1, under)?
+            .filter(|p| p.path().extension().is_some_and(|ext| ext == "mkv"))
+            .filter(|p| {
+                p.depth() <= max_depth
+                    || p.path()
+                        .parent()
+                        .and_then(|dir| dir.file_name())
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| ExtraKind::from_folder(name).is_some())
+            })
             .map(|e| e.into_path())
-            .collect()
+            .collect())
+    }
+
+    /// The exclusions a walk down to `max_depth` follows
+    fn _exclusions(&self, max_depth: usize) -> Result<Cow<'_, Exclusions>> {
+        Ok(match &self.exclusions {
+            Some(exclusions) if exclusions.covers(max_depth) => Cow::Borrowed(exclusions),
+            _ => Cow::Owned(Exclusions::load(&self.root, &self.config.scan.exclude, max_depth)?),
+        })
+    }
+
+    /// Walk the root down to `max_depth`, leaving out paths excluded by
+    /// `scan.exclude` or a `.numovignore` file (and everything below them).
+    /// Only the folders leading to `under`, and everything below it, are walked.
+    fn _walk<'a>(
+        &'a self,
+        max_depth: usize,
+        under: &'a Path,
+    ) -> Result<impl Iterator<Item = walkdir::DirEntry> + 'a> {
+        let exclusions = self._exclusions(max_depth)?;
+        Ok(WalkDir::new(&self.root)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_entry(move |e| {
+                (e.path().starts_with(under) || under.starts_with(e.path()))
+                    && exclusions.reason(e.path(), e.file_type().is_dir()).is_none()
+            })
+            .filter_map(|f| f.ok()))
     }
 
-    fn map_ratings(&mut self) {
+    /// Every film below the root as its files (several for a multi-part film),
+    /// keyed the same way as the collection
+    fn _get_units(&self) -> Result<Vec<(u32, Vec<PathBuf>)>> {
+        let path_list = self._get_dirs()?;
+        Ok(Self::group_by_movie_dir(&path_list)
+            .into_values()
+            .flat_map(|paths| stack::group(paths.into_iter().filter(|p| Extra::detect(p).is_none())))
+            .filter_map(|unit| Some((stack::hash(&unit).ok()?, unit.into_iter().cloned().collect())))
+            .collect())
+    }
+
+    fn group_by_movie_dir(paths: &[PathBuf]) -> BTreeMap<PathBuf, Vec<&PathBuf>> {
+        let mut groups: BTreeMap<PathBuf, Vec<&PathBuf>> = BTreeMap::new();
+        for path in paths {
+            groups.entry(Extra::movie_dir(path)).or_default().push(path);
+        }
+        groups
+    }
+
+    /// Give each movie the rating whose title is most like its own. Returns the number of rated movies.
+    fn map_ratings(&mut self) -> usize {
         let mut count = 0;
         for movie in self.collection.values_mut() {
-            let mut best_match = (0.88, None);
+            let mut best_match = (self.config.ratings.match_threshold, None);
 
+            // Scraped titles have ':' replaced by '-', as folder names can not hold it
+            let title = movie.title.replace(':', "-");
             for (rating_title, rating_value) in &self.ratings {
-                let similarity = strsim::jaro_winkler(&movie.title, rating_title);
+                let similarity = strsim::jaro_winkler(&title, rating_title);
 
                 if similarity > best_match.0 {
                     best_match = (similarity, Some(rating_value.clone()))
                 }
             }
-            if best_match.0 > 0.9 {
+            if best_match.1.is_some() {
+                if movie.rating != best_match.1 {
+                    self.dirty.movies.insert(movie.hash);
+                }
                 movie.rating = best_match.1;
                 count += 1;
             }
         }
-        if count > 0 {
-            println!("Successfully mapped ratings to {count} movies.")
-        }
+        count
     }
 }
 
-struct Prog {
-    pub pb: pbr::ProgressBar<Stdout>,
-    t1: Instant,
-    job: String,
-}
-
-impl Prog {
-    fn new(total: usize, job: &str) -> Self {
-        Prog {
-            pb: pbr::ProgressBar::new(total as u64),
-            t1: Instant::now(),
-            job: job.to_string(),
-        }
-    }
-
-    fn inc(&mut self) {
-        self.pb.inc();
-    }
-
-    fn end(&mut self) {
-        let output = format!(
-            "\tFinished {} in {:.4?}",
-            self.job,
-            Instant::now() - self.t1
-        );
-        self.pb.finish_println(&output);
-        println!();
-    }
+/// Hashes of the movies and extras that differ from the database
+#[derive(Debug, Default)]
+struct Dirty {
+    /// Added or changed
+    movies: HashSet<u32>,
+    removed: HashSet<u32>,
+    /// Added or re-linked
+    extras: HashSet<u32>,
+    removed_extras: HashSet<u32>,
 }
 
 struct Logger {
@@ -411,41 +1716,19 @@ impl Logger {
         self.new.is_empty() && self.removed.is_empty() && self.updated.is_empty()
     }
 
-    fn output(self) {
-        let max_len = *[self.new.len(), self.removed.len(), self.updated.len()]
-            .iter()
-            .max()
-            .unwrap();
-
-        let hashsets = [
-            ("New", self.new),
-            ("Removed", self.removed),
-            ("Updated", self.updated),
-        ];
-
-        let output_vec = hashsets
-            .into_iter()
-            .filter_map(|this_set| {
-                if this_set.1.is_empty() {
-                    return None;
-                }
-                let this_len = this_set.1.len();
-                let mut vec = this_set.1.into_iter().collect::<Vec<_>>();
-                vec.sort();
-                vec.extend(repeat("".to_string()).take(max_len.saturating_sub(this_len)));
-
-                Some(Series::new(&format!("{} ({})", this_set.0, this_len), vec))
-            })
-            .collect();
-
-        let df = match DataFrame::new(output_vec) {
-            Ok(df) => df,
-            Err(e) => {
-                println!("Could not create dataframe for updated values.\nError: {e}");
-                return;
-            }
+    /// The names sorted, with the number of extras saved and removed
+    fn report(self, extras_saved: usize, extras_removed: usize) -> ScanReport {
+        let sorted = |names: HashSet<String>| {
+            let mut names = names.into_iter().collect::<Vec<_>>();
+            names.sort();
+            names
         };
-
-        println!("{:?}", df);
+        ScanReport {
+            added: sorted(self.new),
+            updated: sorted(self.updated),
+            removed: sorted(self.removed),
+            extras_saved,
+            extras_removed,
+        }
     }
 }
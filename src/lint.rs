@@ -0,0 +1,223 @@
+use crate::{
+    exclude::IGNORE_FILE,
+    extras::Extra,
+    movie_types::extra_kind::ExtraKind,
+    pattern::{NamePattern, Target},
+    stack,
+};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+use walkdir::DirEntry;
+
+/// Video containers numov does not read
+const OTHER_VIDEO: &[&str] = &["avi", "mp4", "m4v", "ts", "m2ts", "mov", "wmv", "mpg", "mpeg", "webm"];
+
+/// A layout problem found by `Library::lint`
+#[derive(Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Problem {
+    /// A movie folder whose name matches no folder pattern
+    BadName,
+    /// Several MKVs in a movie folder that are not parts of one film
+    SeveralMkvs,
+    /// A movie folder without any video
+    NoVideo,
+    /// A video in a container numov does not read
+    NonMkvVideo,
+    /// A folder with nothing in it
+    EmptyFolder,
+    /// A file directly under the root
+    StrayFile,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Problem::BadName => "Name does not match a pattern",
+            Problem::SeveralMkvs => "Several MKVs",
+            Problem::NoVideo => "No video",
+            Problem::NonMkvVideo => "Non-MKV video",
+            Problem::EmptyFolder => "Empty folder",
+            Problem::StrayFile => "Stray file at root",
+        };
+        write!(f, "{text}")
+    }
+}
+
+/// A problem and where it was found
+#[derive(Debug, Serialize)]
+pub struct Issue {
+    /// What is wrong
+    pub problem: Problem,
+    /// Relative to the library root
+    pub path: PathBuf,
+}
+
+#[derive(Default)]
+struct Folder {
+    depth: usize,
+    entries: usize,
+    subfolders: usize,
+    mkvs: Vec<PathBuf>,
+    other_videos: usize,
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+fn is_extras_folder(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| ExtraKind::from_folder(n).is_some())
+}
+
+/// Check the layout of a library from the entries of a walk over its root.
+/// Movie folders sit `max_depth - 1` below the root, or higher up inside
+/// collection folders; anything deeper is only checked for non-MKV videos.
+pub fn check(root: &Path, entries: Vec<DirEntry>, max_depth: usize, patterns: &[NamePattern]) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut folders: BTreeMap<PathBuf, Folder> = BTreeMap::new();
+    let mut issue = |problem, path: &Path| {
+        let path = path.strip_prefix(root).unwrap_or(path).to_path_buf();
+        issues.push(Issue { problem, path });
+    };
+
+    for entry in entries.iter().filter(|e| e.depth() > 0) {
+        let path = entry.path();
+        let Some(parent) = path.parent() else {
+            continue;
+        };
+        let parent = folders.entry(parent.to_path_buf()).or_default();
+        parent.entries += 1;
+
+        if entry.file_type().is_dir() {
+            if !is_extras_folder(path) {
+                parent.subfolders += 1;
+            }
+            folders.entry(path.to_path_buf()).or_default().depth = entry.depth();
+            continue;
+        }
+
+        match extension(path).as_str() {
+            "mkv" => parent.mkvs.push(path.to_path_buf()),
+            ext if OTHER_VIDEO.contains(&ext) => {
+                parent.other_videos += 1;
+                issue(Problem::NonMkvVideo, path);
+            }
+            _ => {}
+        }
+        let name = entry.file_name().to_string_lossy();
+        if entry.depth() == 1 && name != IGNORE_FILE && !name.starts_with('.') {
+            issue(Problem::StrayFile, path);
+        }
+    }
+
+    let folder_patterns = patterns
+        .iter()
+        .chain(NamePattern::defaults())
+        .filter(|p| p.target == Target::Folder)
+        .collect::<Vec<_>>();
+
+    for (path, folder) in &folders {
+        if folder.depth == 0 || folder.depth >= max_depth || is_extras_folder(path) {
+            continue;
+        }
+
+        if folder.entries == 0 {
+            issue(Problem::EmptyFolder, path);
+        } else if !folder.mkvs.is_empty() || folder.other_videos > 0 {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if !folder_patterns.iter().any(|p| p.captures(&name).is_some()) {
+                issue(Problem::BadName, path);
+            }
+            let films = stack::group(folder.mkvs.iter().filter(|p| Extra::detect(p).is_none()));
+            if films.len() > 1 {
+                issue(Problem::SeveralMkvs, path);
+            }
+        } else if folder.subfolders == 0 || folder.depth == max_depth - 1 {
+            issue(Problem::NoVideo, path);
+        }
+    }
+
+    issues.sort_by(|a, b| (&a.path, &a.problem).cmp(&(&b.path, &b.problem)));
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use walkdir::WalkDir;
+
+    /// Empty files at `files` below a fresh root, and the problems `check` finds there
+    fn lint(name: &str, files: &[&str], dirs: &[&str], max_depth: usize) -> Vec<(Problem, String)> {
+        let root = std::env::temp_dir().join(format!("numov-lint-{name}-{}", std::process::id()));
+        for dir in dirs {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let entries = WalkDir::new(&root).max_depth(max_depth).into_iter().filter_map(|e| e.ok()).collect();
+        let issues = check(&root, entries, max_depth, &[]);
+        fs::remove_dir_all(&root).unwrap();
+        issues
+            .into_iter()
+            .map(|i| (i.problem, i.path.to_string_lossy().into_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn each_layout_problem_is_found_where_it_is() {
+        let issues = lint(
+            "flat",
+            &[
+                ".numovignore",
+                "notes.txt",
+                "Heat (1995)/Heat.mkv",
+                "Heat (1995)/Trailers/Heat.mkv",
+                "Alien (1979)/Alien.cd1.mkv",
+                "Alien (1979)/Alien.cd2.mkv",
+                "Brazil (1985)/Brazil.avi",
+                "Double Bill (2000)/First.mkv",
+                "Double Bill (2000)/Second.mkv",
+                "heat_1995/Heat.mkv",
+                "Posters (2002)/poster.jpg",
+            ],
+            &["Empty (2001)"],
+            2,
+        );
+        assert_eq!(
+            issues,
+            [
+                (Problem::NonMkvVideo, "Brazil (1985)/Brazil.avi".to_string()),
+                (Problem::SeveralMkvs, "Double Bill (2000)".to_string()),
+                (Problem::EmptyFolder, "Empty (2001)".to_string()),
+                (Problem::NoVideo, "Posters (2002)".to_string()),
+                (Problem::BadName, "heat_1995".to_string()),
+                (Problem::StrayFile, "notes.txt".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn collection_folders_need_no_video_of_their_own() {
+        let issues = lint(
+            "collections",
+            &["Alien Collection/Alien (1979)/Alien.mkv", "Alien Collection/Covers/front.jpg"],
+            &[],
+            3,
+        );
+        assert_eq!(issues, [(Problem::NoVideo, "Alien Collection/Covers".to_string())]);
+    }
+}
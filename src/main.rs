@@ -1,106 +1,492 @@
-mod database;
-mod library;
-mod movie;
-mod movie_types;
+mod report;
 
-use clap::{Parser, ValueEnum};
-use library::Library;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use numov::{database, Config, Database, Error, Library, Movie, Overwrite, Query};
 use std::io::{self, Write};
-use std::{env, path::PathBuf, time::Instant};
+use std::{
+    path::{self, Path, PathBuf},
+    time::Instant,
+};
 
 fn main() {
     let t1 = Instant::now();
-    set_env();
-    let args = Args::parse();
-
-    if args.reset {
-        print!("If you wish to completely reset the database, type \'KILL IT\' (without the quotes) » ");
-        let _ = io::stdout().flush();
-        let mut input = String::new();
-        if io::stdin().read_line(&mut input).is_ok() && input.trim() == "KILL IT" {
-            database::delete_db();
-            std::process::exit(0);
-        } else {
-            println!("Database was not deleted. Exiting program.");
-            std::process::exit(0);
-        }
+    let cli = Cli::parse();
+    let mut config = Config::load_profile(cli.profile.as_deref()).unwrap_or_else(|e| {
+        eprintln!("Could not load config!\nError: {e}");
+        std::process::exit(e.exit_code());
+    });
+    if let Some(db) = &cli.db {
+        // Relative to where numov is run, unlike `db` in the config file
+        config.db = Some(path::absolute(db).unwrap_or_else(|_| db.clone()));
     }
+    cli.command.apply(&mut config);
+    config.tables.apply();
+    let timed = matches!(
+        cli.command,
+        Command::Scan { .. }
+            | Command::Rename { .. }
+            | Command::Export { .. }
+            | Command::Ratings { .. }
+    );
 
-    let mut lib = Library::new(PathBuf::new());
+    let code = match cli.command {
+        Command::Info { file } => info(&file, &config),
+        Command::Db {
+            action: DbAction::Reset,
+        } => {
+            match config.db_path() {
+                Ok(db_path) => {
+                    reset(&db_path);
+                    0
+                }
+                Err(e) => failed("find database", e),
+            }
+        }
+        Command::Db {
+            action: DbAction::Path,
+        } => {
+            match config.db_path() {
+                Ok(db_path) => {
+                    println!("{}", db_path.display());
+                    0
+                }
+                Err(e) => failed("find database", e),
+            }
+        }
+        command => {
+            let lib = config
+                .db_path()
+                .and_then(|db_path| Database::open_at(&db_path))
+                .and_then(|db| Library::from_parts(PathBuf::new(), db, config));
+            let mut lib = lib.unwrap_or_else(|e| {
+                eprintln!("Could not open library!\nError: {e}");
+                std::process::exit(e.exit_code());
+            });
+            if lib.movies().next().is_some() || lib.rating_count() > 0 {
+                eprintln!(
+                    "Read in {} movies and {} ratings from database.",
+                    lib.movies().count(),
+                    lib.rating_count()
+                );
+            }
+            match (run(&mut lib, command, cli.explain), lib.close()) {
+                (0, Err(e)) => failed("close database", e),
+                (code, _) => code,
+            }
+        }
+    };
 
-    if let Some(user) = &args.lb_username {
-        lib.update_ratings(&user)
-            .unwrap_or_else(|e| println!("Error parsing ratings: {e}"));
+    if timed {
+        println!("\nCompleted all tasks in {:.4?}", Instant::now() - t1);
     }
+    std::process::exit(code);
+}
 
-    match &args.path {
-        Some(root) if PathBuf::from(&root).is_dir() => {
-            lib.root = PathBuf::from(&root)
-                .canonicalize()
-                .unwrap_or_else(|_| PathBuf::from(&root));
-
-            lib.update_movies();
-            if args.rename {
-                lib.rename_folders();
+/// Run a command that needs the library, returning the exit code
+fn run(lib: &mut Library, command: Command, explain: bool) -> i32 {
+    if command.needs_root() {
+        // A path on the command line wins over the root of the config or profile
+        let Some(root) = command.path().or(lib.config().root.as_deref()).map(Path::to_path_buf) else {
+            eprintln!("No path given, and no root set in the config.");
+            return 2;
+        };
+        if let Err(e) = set_root(lib, &root) {
+            return failed("open library", e);
+        }
+        if explain {
+            if let Err(e) = report::explain(lib) {
+                return failed("read exclude rules", e);
             }
         }
-        _ => println!("Invalid path provided."),
-    };
+    }
 
-    if args.csv {
-        lib.output_to_csv();
+    match command {
+        Command::Scan { tv, .. } => report::scan(lib, tv)
+            .and_then(|_| report::finish_checksums(lib))
+            .map_or_else(|e| failed("scan library", e), |_| 0),
+        Command::Watch { .. } => report::watch(lib).map_or_else(|e| failed("watch library", e), |_| 0),
+        Command::Rename { .. } => report::scan(lib, false)
+            .and_then(|_| report::rename(lib))
+            .and_then(|_| report::finish_checksums(lib))
+            .map_or_else(|e| failed("rename folders", e), |_| 0),
+        Command::Export {
+            format: ExportFormat::Csv { output, filter },
+        } => {
+            lib.genre = filter.genre;
+            lib.best_only = filter.best_only;
+            lib.output_to_csv(&output).map_or_else(
+                |e| failed("write csv", e),
+                |_| {
+                    println!("Successfully wrote data to {}", output.display());
+                    0
+                },
+            )
+        }
+        Command::Export {
+            format: ExportFormat::Nfo {
+                overwrite, dry_run, ..
+            },
+        } => report::export_nfo(lib, overwrite.into(), dry_run)
+            .map_or_else(|e| failed("write nfo files", e), |_| 0),
+        Command::Ratings { user } => report::ratings(lib, &user)
+            .map_or_else(|e| failed("scrape ratings", e), |_| 0),
+        Command::Query {
+            title,
+            year,
+            filter,
+            json,
+        } => {
+            let query = Query {
+                title,
+                year,
+                genre: filter.genre,
+                best_only: filter.best_only,
+            };
+            report::query(lib, &query, json)
+                .map_or_else(|e| failed("query library", e), |_| 0)
+        }
+        Command::Stats { table, filter, .. } => {
+            lib.genre = filter.genre;
+            lib.best_only = filter.best_only;
+            report::stats(lib, table.as_str())
+                .map_or_else(|e| failed("create dataframe", e), |_| 0)
+        }
+        Command::Lint { json, .. } => report::lint(lib, json)
+            .map_or_else(|e| failed("lint library", e), findings),
+        Command::Verify { json, .. } => report::verify(lib, json)
+            .map_or_else(|e| failed("verify library", e), findings),
+        Command::Fsck { json, .. } => report::fsck(lib, json)
+            .map_or_else(|e| failed("check library", e), findings),
+        Command::Compare { a, b, json } => report::compare(lib, a, b, json)
+            .map_or_else(|e| failed("compare libraries", e), findings),
+        Command::PlanBackup {
+            capacities,
+            by_rating,
+            dry_run,
+        } => report::plan_backup(lib, &capacities, by_rating, dry_run)
+            .map_or_else(|e| failed("plan backup", e), |_| 0),
+        Command::BenchProbe { .. } => report::bench_probe(lib)
+            .map_or_else(|e| failed("benchmark probe", e), |_| 0),
+        Command::Info { .. } | Command::Db { .. } => unreachable!("runs without a library"),
     }
+}
 
-    if let Some(x) = &args.dataframe {
-        lib.handle_dataframe(x.as_str())
-            .unwrap_or_else(|e| println!("Error creating dataframe: {e}"))
+/// Read one file and print what numov finds in it
+fn info(file: &Path, config: &Config) -> i32 {
+    let mut events = report::Events::new("reading file");
+    match Movie::probe(file, config, &mut |e| events.handle(e)) {
+        Ok(movie) => {
+            println!("{movie}");
+            0
+        }
+        Err(e) => failed("read file", e),
     }
+}
 
-    // Getting library to close from within the library crate
-    // has proven to be very difficult
-    lib.db
-        .conn
-        .close()
-        .unwrap_or_else(|(_, e)| println!("Could not close database: {e}"));
+fn reset(db_path: &Path) {
+    print!(
+        "If you wish to completely reset the database, type \'KILL IT\' (without the quotes) » "
+    );
+    let _ = io::stdout().flush();
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input).is_ok() && input.trim() == "KILL IT" {
+        match database::delete_db(db_path) {
+            Ok(true) => println!("Successfully deleted database!"),
+            Ok(false) => println!("Database file not found."),
+            Err(e) => println!("Unable to delete database: {e}"),
+        }
+    } else {
+        println!("Database was not deleted. Exiting program.");
+    }
+}
+
+fn set_root(lib: &mut Library, path: &Path) -> numov::Result<()> {
+    lib.root = path.canonicalize().map_err(Error::io(path))?;
+    if !lib.root.is_dir() {
+        return Err(Error::io(path)(io::ErrorKind::NotADirectory.into()));
+    }
+    Ok(())
+}
 
-    println!("\nCompleted all tasks in {:.4?}", Instant::now() - t1);
+/// Print why a command failed, and return its exit code
+fn failed(task: &str, e: Error) -> i32 {
+    eprintln!("Could not {task}: {e}");
+    e.exit_code()
+}
+
+/// Commands that look for problems exit with 1 if they found any
+fn findings(count: usize) -> i32 {
+    i32::from(count > 0)
 }
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about)]
-struct Args {
-    /// Path to read movies from
-    #[arg(short = 'P', long)]
-    path: Option<String>,
+struct Cli {
+    /// Use the roots, database and settings of this profile from the config file
+    #[arg(short, long, global = true)]
+    profile: Option<String>,
+
+    /// Database file to use instead of the one in the config, relative to the current directory
+    #[arg(long, global = true)]
+    db: Option<PathBuf>,
+
+    /// Print which paths were skipped by exclude rules, and why
+    #[arg(long, global = true, action = clap::ArgAction::SetTrue)]
+    explain: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Read the movies under a path into the database
+    Scan {
+        /// Path to read movies from; defaults to the root in the config
+        path: Option<PathBuf>,
+
+        /// Treat the path as a TV library (Show (Year)/Season 01/S01E01.mkv)
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        tv: bool,
+    },
+
+    /// Scan a path, then keep running and rescan its movie folders as they change
+    Watch {
+        /// Path to read movies from; defaults to the root in the config
+        path: Option<PathBuf>,
+    },
+
+    /// Scan a path, then rename its movie folders in a standard, readable fashion
+    Rename {
+        /// Path to read movies from; defaults to the root in the config
+        path: Option<PathBuf>,
+
+        /// Folder name template, instead of `rename.format` from the config
+        #[arg(long)]
+        format: Option<String>,
+    },
+
+    /// Write the movies in the database to a csv file, or to nfo files next to them
+    Export {
+        #[command(subcommand)]
+        format: ExportFormat,
+    },
+
+    /// Scrape the ratings of a letterboxd user and map them to movies
+    Ratings {
+        /// Letterboxd username (the profile must be public)
+        user: String,
+    },
+
+    /// Look up movies in the database
+    Query {
+        /// Part of the title
+        title: Option<String>,
+
+        /// Release year
+        #[arg(short = 'Y', long)]
+        year: Option<i16>,
 
-    /// Letterboxd user ratings to scrape
-    #[arg(short = 'L', long = "letterboxd")]
-    lb_username: Option<String>,
+        #[command(flatten)]
+        filter: Filter,
 
-    /// Rename folders
-    #[arg(short = 'R', long = "rename", action = clap::ArgAction::SetTrue)]
-    rename: bool,
+        /// Print the movies as JSON
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        json: bool,
+    },
 
-    /// Output movie data as a csv file
-    #[arg(short = 'C', long, action = clap::ArgAction::SetTrue)]
-    csv: bool,
+    /// Print a table about the movies in the database
+    Stats {
+        #[arg(value_enum)]
+        table: DFOpts,
 
-    /// Output movie data as a dataframe
-    #[arg(short = 'D', long, value_enum)]
-    dataframe: Option<DFOpts>,
-    //
-    /// Reset database
-    #[arg(long, action = clap::ArgAction::SetTrue)]
-    reset: bool,
+        /// Rows to print, instead of `tables.stats_rows` from the config
+        #[arg(long)]
+        rows: Option<usize>,
+
+        #[command(flatten)]
+        filter: Filter,
+    },
+
+    /// Read one file and print what numov finds in it, without storing anything
+    Info { file: PathBuf },
+
+    /// Report layout problems under a path; exits non-zero if any are found
+    Lint {
+        path: Option<PathBuf>,
+
+        /// Print the problems as JSON
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        json: bool,
+    },
+
+    /// Check the files under a path for truncation and broken structure, and store the results; exits non-zero if any are damaged
+    Verify {
+        path: Option<PathBuf>,
+
+        /// Print the damaged movies as JSON
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        json: bool,
+    },
+
+    /// Hash files under a path again and report those whose content changed while size and mtime did not; exits non-zero if any did
+    Fsck {
+        path: Option<PathBuf>,
+
+        /// Print the changed files as JSON
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        json: bool,
+    },
+
+    /// Compare the films under two roots by content and title, year and edition; exits non-zero if they differ
+    Compare {
+        #[arg(value_name = "ROOT_A")]
+        a: PathBuf,
+
+        #[arg(value_name = "ROOT_B")]
+        b: PathBuf,
+
+        /// Print the differences as JSON
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        json: bool,
+    },
+
+    /// Plan which films go on which backup drive, given drive capacities in GB (reuses the stored drives if none are given)
+    PlanBackup {
+        #[arg(value_name = "GB")]
+        capacities: Vec<f32>,
+
+        /// Place higher rated films first
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        by_rating: bool,
+
+        /// Show the plan without saving it
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        dry_run: bool,
+    },
+
+    /// Compare bytes read and time per file of the header-only probe against a full Matroska::open
+    BenchProbe { path: Option<PathBuf> },
+
+    /// Manage the database
+    Db {
+        #[command(subcommand)]
+        action: DbAction,
+    },
+}
+
+impl Command {
+    /// Whether the command works on the files under a library root
+    fn needs_root(&self) -> bool {
+        matches!(
+            self,
+            Command::Scan { .. }
+                | Command::Watch { .. }
+                | Command::Rename { .. }
+                | Command::Lint { .. }
+                | Command::Verify { .. }
+                | Command::Fsck { .. }
+                | Command::BenchProbe { .. }
+                | Command::Export {
+                    format: ExportFormat::Nfo { .. }
+                }
+        )
+    }
+
+    /// The library root given on the command line
+    fn path(&self) -> Option<&Path> {
+        match self {
+            Command::Scan { path, .. }
+            | Command::Watch { path }
+            | Command::Rename { path, .. }
+            | Command::Lint { path, .. }
+            | Command::Verify { path, .. }
+            | Command::Fsck { path, .. }
+            | Command::BenchProbe { path }
+            | Command::Export {
+                format: ExportFormat::Nfo { path, .. },
+            } => path.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Put the command's options over the settings they replace
+    fn apply(&self, config: &mut Config) {
+        match self {
+            Command::Rename {
+                format: Some(format),
+                ..
+            } => config.rename.format = format.clone(),
+            Command::Stats { rows: Some(rows), .. } => config.tables.stats_rows = *rows,
+            _ => {}
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum ExportFormat {
+    /// Write the movies in the database to a csv file
+    Csv {
+        /// File to write
+        #[arg(short, long, default_value = "m_log.csv")]
+        output: PathBuf,
+
+        #[command(flatten)]
+        filter: Filter,
+    },
+    /// Write a Kodi/Jellyfin movie.nfo into the folder of each scanned movie under a path (<file name>.nfo if the folder holds several)
+    Nfo {
+        /// Path to write nfo files under; defaults to the root in the config
+        path: Option<PathBuf>,
+
+        /// When to replace existing nfo files
+        #[arg(long, value_enum, default_value = "never")]
+        overwrite: NfoOverwrite,
+
+        /// Show which nfo files would be written without writing them
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+struct Filter {
+    /// Only output movies of the given genre (read from .nfo files)
+    #[arg(short = 'G', long)]
+    genre: Option<String>,
+
+    /// Only output the best version of each work (title, year and edition)
+    #[arg(short = 'B', long, action = clap::ArgAction::SetTrue)]
+    best_only: bool,
+}
+
+/// When to replace existing nfo files, mirroring [`Overwrite`] for the command line
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum NfoOverwrite {
+    /// Never replace an existing nfo
+    Never,
+    /// Only replace nfo files previously written by numov
+    Numov,
+    /// Always replace
+    Always,
+}
+
+impl From<NfoOverwrite> for Overwrite {
+    fn from(policy: NfoOverwrite) -> Self {
+        match policy {
+            NfoOverwrite::Never => Overwrite::Never,
+            NfoOverwrite::Numov => Overwrite::Numov,
+            NfoOverwrite::Always => Overwrite::Always,
+        }
+    }
 }
 
-fn set_env() {
-    env::set_var("POLARS_FMT_TABLE_FORMATTING", "UTF8_BORDERS_ONLY");
-    env::set_var("POLARS_FMT_TABLE_HIDE_DATAFRAME_SHAPE_INFORMATION", "1");
-    env::set_var("POLARS_FMT_TABLE_ROUNDED_CORNERS", "1");
-    env::set_var("POLARS_FMT_TABLE_HIDE_COLUMN_DATA_TYPES", "1");
-    env::set_var("POLARS_FMT_MAX_ROWS", "25");
-    env::set_var("POLARS_FMT_STR_LEN", "35");
+#[derive(Subcommand, Debug)]
+enum DbAction {
+    /// Delete the database
+    Reset,
+    /// Print where the database is
+    Path,
 }
 
 #[allow(non_camel_case_types)]
@@ -108,8 +494,17 @@ fn set_env() {
 enum DFOpts {
     audio,
     channels,
+    collections,
+    editions,
+    extras,
     full,
+    genres,
+    patterns,
+    seasons,
+    sidecars,
     subs,
+    verified,
+    versions,
     year,
 }
 
@@ -118,8 +513,17 @@ impl DFOpts {
         match self {
             DFOpts::audio => "audio",
             DFOpts::channels => "channels",
+            DFOpts::collections => "collections",
+            DFOpts::editions => "editions",
+            DFOpts::extras => "extras",
             DFOpts::full => "full",
+            DFOpts::genres => "genres",
+            DFOpts::patterns => "patterns",
+            DFOpts::seasons => "seasons",
+            DFOpts::sidecars => "sidecars",
             DFOpts::subs => "subs",
+            DFOpts::verified => "verified",
+            DFOpts::versions => "versions",
             DFOpts::year => "year",
         }
     }
@@ -1,152 +1,449 @@
 use crate::movie_types::{
-    audio_codec::AudioCodec, bitdepth::BitDepth, resolution::Resolution,
-    sub_format::SubtitleFormat, video_codec::VideoCodec,
+    audio_codec::AudioCodec, bitdepth::BitDepth, hdr::Hdr, resolution::Resolution,
+    source::Source, sub_format::SubtitleFormat, video_codec::VideoCodec,
+};
+use crate::{
+    config::Config,
+    edition,
+    error::{self, Error},
+    library::ScanEvent,
+    nfo::Nfo,
+    pattern::{NamePattern, Target, TitleInfo},
+    release::Release,
+    sidecar,
 };
 use core::time::Duration;
 use matroska::{
     self, Matroska,
     Settings::{Audio, Video},
-    Track, Tracktype,
+    TagValue, Track, Tracktype,
 };
-use regex::Regex;
 use std::{
     borrow::Cow,
     fmt::{Display, Formatter, Result},
 };
-use std::{path::Path, sync::LazyLock};
+use std::path::{Path, PathBuf};
 use xxhash_rust::const_xxh32::xxh32;
 
-static RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"(?P<title>.*) \((?P<year>\d{4})\)").unwrap());
-
+/// The first video track
 #[derive(Debug)]
 pub struct VideoStream {
+    /// Resolution class, from the frame height
     pub resolution: Resolution,
+    /// Codec of the video track
     pub codec: VideoCodec,
+    /// Bits per colour channel
     pub bit_depth: BitDepth,
+    /// HDR format
+    pub hdr: Hdr,
 }
 
+/// Codec, channels and count describe the first audio track
 #[derive(Debug)]
 pub struct AudioStream {
+    /// Codec of the first track
     pub codec: AudioCodec,
+    /// Channels of the first track, such as 5.1
     pub channels: f32,
+    /// Number of audio tracks
     pub count: usize,
+    /// Every audio track, in file order
+    pub tracks: Vec<AudioTrack>,
 }
 
+/// One audio track
+#[derive(Debug)]
+pub struct AudioTrack {
+    /// Codec of the track
+    pub codec: AudioCodec,
+    /// Channel layout, such as 2.0 or 7.1
+    pub channels: f32,
+    /// Language code, such as `eng`
+    pub language: String,
+}
+
+/// Format and count describe the first subtitle track
 #[derive(Debug)]
 pub struct SubtitleStream {
+    /// Format of the first track
     pub format: SubtitleFormat,
+    /// Number of subtitle tracks, embedded and external
     pub count: usize,
+    /// Every subtitle track, embedded first
+    pub tracks: Vec<SubtitleTrack>,
+}
+
+/// One subtitle track, embedded or in a file next to the video
+#[derive(Debug)]
+pub struct SubtitleTrack {
+    /// Format of the track
+    pub format: SubtitleFormat,
+    /// Language code, such as `eng`
+    pub language: String,
+    /// Forced track, for dialogue in another language than the audio
+    pub forced: bool,
+    /// File name of an external (sidecar) subtitle; `None` for embedded tracks
+    pub file: Option<String>,
 }
 
+impl SubtitleStream {
+    /// Replace the external tracks with those found next to the video,
+    /// returning whether anything changed
+    pub fn set_external(&mut self, external: Vec<SubtitleTrack>) -> bool {
+        let old = self.tracks.iter().filter_map(|t| t.file.as_deref());
+        if old.eq(external.iter().filter_map(|t| t.file.as_deref())) {
+            return false;
+        }
+
+        self.tracks.retain(|t| t.file.is_none());
+        self.tracks.extend(external);
+        self.count = self.tracks.len();
+        if let Some(first) = self.tracks.first() {
+            self.format = first.format.clone();
+        }
+        true
+    }
+}
+
+/// One version of a film: a file, or the parts of a multi-part film
 #[derive(Debug)]
 pub struct Movie {
+    /// Title, from the metadata, nfo or file name
     pub title: String,
+    /// Release year
     pub year: i16,
+    /// Edition, such as `Director's Cut`
+    pub edition: Option<String>,
+    /// IMDb id, such as `tt0113277`
+    pub imdb: Option<String>,
+    /// Name of the title pattern that matched the file name, if any
+    pub pattern: Option<String>,
+    /// Title in the original language, as listed in an nfo file
+    pub original_title: Option<String>,
+    /// TMDb id
+    pub tmdb: Option<String>,
+    /// Genres, as listed in an nfo file
+    pub genres: Vec<String>,
+    /// Runtime in minutes, as listed in an nfo file
+    pub runtime: Option<u32>,
+    /// Rating out of 10, as listed in an nfo file
+    pub user_rating: Option<f32>,
+    /// Letterboxd rating, in stars
     pub rating: Option<String>,
+    /// Size of all parts, in GB
     pub size: f32,
+    /// Duration, such as `2h 50min`
     pub duration: String,
+    /// The video track
     pub video: VideoStream,
+    /// The audio tracks
     pub audio: AudioStream,
+    /// The subtitle tracks
     pub subs: SubtitleStream,
+    /// Source of the release, such as Remux or WEB-DL
+    pub source: Option<Source>,
+    /// Release group
+    pub group: Option<String>,
+    /// File names of a multi-part film, in order; empty for a single file
+    pub parts: Vec<String>,
+    /// Folders between the library root and the movie's folder, e.g. `Middle-earth Collection`
+    pub collection: Option<String>,
+    /// Hash of the file's size, mtime and path, which identifies it in the library
     pub hash: u32,
 }
 
 impl Movie {
-    pub fn new(path: impl AsRef<Path>) -> Self {
-        let matroska = Matroska::open(std::fs::File::open(path.as_ref()).unwrap()).unwrap();
-        Self::collect(&matroska, path.as_ref())
+    /// Read one movie file on its own, without a library. Only the headers are
+    /// read, and nothing is written to the file. Problems that do not stop the
+    /// file from being read, such as a broken nfo, are passed to `on_event`.
+    pub fn probe(path: &Path, config: &Config, on_event: &mut dyn FnMut(ScanEvent)) -> error::Result<Self> {
+        let probe = crate::probe::open(path, config.scan.read_budget())?;
+        Self::collect(&probe.matroska, path, config, false, on_event)
     }
 
-    fn collect(matroska: &Matroska, path: &Path) -> Self {
-        let (title, year) = Self::get_title_year(matroska, path).unwrap();
-        let (byte_count, hash) = Self::read_metadata(path);
-        let duration = Self::readable_duration(&matroska.info.duration.unwrap());
+    pub(crate) fn collect(
+        matroska: &Matroska,
+        path: &Path,
+        config: &Config,
+        write_title: bool,
+        on_event: &mut dyn FnMut(ScanEvent),
+    ) -> error::Result<Self> {
+        let (byte_count, hash) = Self::read_metadata(path)?;
+        let nfo = match Nfo::find(path).map(Nfo::read) {
+            Some(Ok(nfo)) => nfo,
+            Some(Err(e)) => {
+                on_event(ScanEvent::Warning(&e));
+                Nfo::default()
+            }
+            None => Nfo::default(),
+        };
+        let (title_info, from_file_name) = Self::get_title_year(matroska, path, &config.patterns, &nfo)
+            .ok_or_else(|| Error::unusable(path, "no title and year in its name, metadata or nfo"))?;
+        if from_file_name && write_title {
+            let title = format!("{} ({})", title_info.title, title_info.year);
+            match Self::mkvinfo_update(&title, path) {
+                Ok(true) => on_event(ScanEvent::TitleWritten { path, title: &title }),
+                Ok(false) => {}
+                Err(e) => on_event(ScanEvent::Warning(&e)),
+            }
+        }
+        let duration = matroska
+            .info
+            .duration
+            .ok_or_else(|| Error::unusable(path, "no duration"))?;
+        let duration = Self::readable_duration(&duration);
         let size = Self::make_gb(byte_count);
-        let video = Self::get_video_stream(&matroska.tracks[0]);
+        let mut video = Self::get_video_stream(Self::video_track(matroska, path)?, path);
         let (audio, subs) = Self::process_tracks(&matroska.tracks);
 
-        Movie {
-            title,
-            year,
+        let release = Self::get_release(path);
+        let (source, group) = match release {
+            Some(r) => {
+                if let (Resolution::Err, Some(hint)) = (&video.resolution, r.resolution) {
+                    video.resolution = hint;
+                }
+                (r.source, r.group)
+            }
+            None => (None, None),
+        };
+
+        Ok(Movie {
+            title: title_info.title,
+            year: title_info.year,
+            edition: title_info.edition,
+            imdb: title_info.imdb.or(nfo.imdb),
+            pattern: Some(title_info.pattern),
+            original_title: nfo.original_title,
+            tmdb: nfo.tmdb,
+            genres: nfo.genres,
+            runtime: nfo.runtime,
+            user_rating: nfo.user_rating,
             rating: None,
             duration,
             video,
             audio,
             subs,
+            source,
+            group,
+            parts: Vec::new(),
+            collection: None,
             hash,
             size,
+        })
+    }
+
+    /// Build one movie from the stacked parts of a film (cd1, cd2, ...).
+    /// Metadata comes from the first part; size and duration are summed.
+    /// With `write_title`, a title parsed from the file name is written into the file.
+    pub(crate) fn collect_parts(
+        parts: &[(&PathBuf, Matroska)],
+        hash: u32,
+        config: &Config,
+        write_title: bool,
+        on_event: &mut dyn FnMut(ScanEvent),
+    ) -> error::Result<Self> {
+        let (first_path, first_mkv) = &parts[0];
+        let mut movie = Self::collect(first_mkv, first_path, config, write_title, on_event)?;
+        if parts.len() > 1 {
+            let mut bytes = 0;
+            for (path, _) in parts {
+                bytes += Self::read_metadata(path)?.0;
+            }
+            let duration = parts.iter().filter_map(|(_, mkv)| mkv.info.duration).sum();
+            movie.size = Self::make_gb(bytes);
+            movie.duration = Self::readable_duration(&duration);
+            movie.parts = parts
+                .iter()
+                .map(|(p, _)| p.file_name().unwrap_or_default().to_string_lossy().into_owned())
+                .collect();
         }
+        movie.subs.set_external(sidecar::find(&parts.iter().map(|(p, _)| *p).collect::<Vec<_>>()));
+        movie.hash = hash;
+        Ok(movie)
     }
 
-    fn readable_duration(duration: &Duration) -> String {
+    /// Rough quality score used to rank versions of the same work.
+    /// Resolution outweighs HDR, which outweighs source, bit depth and audio.
+    pub fn quality_score(&self) -> u32 {
+        let resolution = match self.video.resolution {
+            Resolution::Err => 0,
+            Resolution::SD => 1,
+            Resolution::HD720 => 2,
+            Resolution::HD1080 => 3,
+            Resolution::UHD4K => 4,
+            Resolution::UHD8K => 5,
+        };
+        let hdr = match self.video.hdr {
+            Hdr::SDR => 0,
+            Hdr::HDR10 | Hdr::HLG => 1,
+            Hdr::HDR10Plus => 2,
+            Hdr::DolbyVision => 3,
+        };
+        let source = match self.source {
+            Some(Source::Remux) => 5,
+            Some(Source::BluRay) => 4,
+            Some(Source::WebDL) => 3,
+            Some(Source::WebRip) => 2,
+            Some(Source::HDTV) | Some(Source::DVD) => 1,
+            _ => 0,
+        };
+        let bits = match self.video.bit_depth {
+            BitDepth::Bit10 => 1,
+            _ => 0,
+        };
+        let audio = match self.audio.codec {
+            AudioCodec::Atmos | AudioCodec::FLAC | AudioCodec::PCM => 4,
+            AudioCodec::DTS => 3,
+            AudioCodec::EAC3 => 2,
+            AudioCodec::AC3 | AudioCodec::AAC | AudioCodec::OPUS => 1,
+            AudioCodec::Other(_) => 0,
+        };
+
+        resolution * 100_000
+            + hdr * 10_000
+            + source * 1_000
+            + bits * 100
+            + audio * 10
+            + self.audio.channels.round() as u32
+    }
+
+    /// Runtime in minutes, from the nfo if available or else the file's duration
+    pub fn runtime_minutes(&self) -> u32 {
+        self.runtime.unwrap_or_else(|| {
+            let (hours, minutes) = self.duration.split_once("h ").unwrap_or(("0", "0"));
+            let minutes = minutes.trim_end_matches("min");
+            hours.parse::<u32>().unwrap_or(0) * 60 + minutes.parse::<u32>().unwrap_or(0)
+        })
+    }
+
+    pub(crate) fn readable_duration(duration: &Duration) -> String {
         let hours = duration.as_secs() / 3600;
         let minutes = (duration.as_secs() % 3600) / 60;
 
         format!("{}h {:02}min", hours, minutes)
     }
 
+    /// `bytes` in GB, however small the file
     pub fn make_gb(bytes: u64) -> f32 {
-        ["B", "KB", "MB", "GB"]
-            .iter()
-            .fold(bytes as f32, |acc, _| match acc > 1024.0 {
-                true => acc / 1024.0,
-                false => acc,
-            })
+        bytes as f32 / 1024_f32.powi(3)
     }
 
-    // pub fn read_metadata(path: &Path) -> (u64, u32) {
-    pub fn read_metadata(path: impl AsRef<Path>) -> (u64, u32) {
-        let metadata =
-            std::fs::metadata(path.as_ref()).expect("Could not read the files metadata.");
+    /// Size of a file, and the hash of its size, mtime and path that identifies it in the library
+    pub fn read_metadata(path: impl AsRef<Path>) -> error::Result<(u64, u32)> {
+        let path = path.as_ref();
+        let metadata = std::fs::metadata(path).map_err(Error::io(path))?;
 
         let bytes = metadata.len();
         let last_mod = metadata
             .modified()
-            .expect("Could not obtain last modified date of file.")
+            .map_err(Error::io(path))?
             .duration_since(std::time::UNIX_EPOCH)
-            .expect("Could not convert to timestamp.")
+            .unwrap_or_default()
             .as_nanos();
 
-        let hash_input = format!("{}{}{}", bytes, last_mod, &path.as_ref().display());
-        (bytes, xxh32(hash_input.as_bytes(), 0))
+        let hash_input = format!("{}{}{}", bytes, last_mod, &path.display());
+        Ok((bytes, xxh32(hash_input.as_bytes(), 0)))
     }
 
-    fn get_title_year<P>(matroska: &Matroska, path: P) -> Option<(String, i16)>
+    /// Use the nfo file if it provides a title and year, otherwise try each user
+    /// pattern in order, then the default patterns, and finally fall back on
+    /// parsing the name as a release. Editions are split off of the title.
+    fn get_title_year<P>(
+        matroska: &Matroska,
+        path: P,
+        patterns: &[NamePattern],
+        nfo: &Nfo,
+    ) -> Option<(TitleInfo, bool)>
     where
         P: AsRef<Path>,
     {
         let metadata_title = matroska.info.title.clone().unwrap_or_default();
 
         let parent = path.as_ref().parent()?.file_name()?.to_str()?;
+        let file = path.as_ref().file_stem()?.to_str()?;
+
+        let from_nfo = match (&nfo.title, nfo.year) {
+            (Some(title), Some(year)) => Some(TitleInfo {
+                title: title.clone(),
+                year,
+                edition: None,
+                imdb: nfo.imdb.clone(),
+                pattern: "nfo".to_string(),
+            }),
+            _ => None,
+        };
 
-        Self::extract_title_year(metadata_title)
+        let (mut info, from_file_name) = from_nfo
+            .map(|info| (info, false))
             .or_else(|| {
-                Self::extract_title_year(parent).map(|(title, year)| {
-                    Self::mkvinfo_update(&title, year, path.as_ref());
-                    (title, year)
-                })
+                patterns
+                    .iter()
+                    .chain(NamePattern::defaults())
+                    .find_map(|pattern| {
+                        let name = match pattern.target {
+                            Target::Metadata => metadata_title.as_str(),
+                            Target::Folder => parent,
+                            Target::File => file,
+                        };
+                        pattern
+                            .captures(name)
+                            .map(|info| (info, pattern.target != Target::Metadata))
+                    })
             })
             .or_else(|| {
-                println!(
-                    "UNABLE TO PARSE TITLE INFO FOR {{ {:?} }}",
-                    &path.as_ref().file_name().unwrap()
-                );
-                None
-            })
+                Self::get_release(path.as_ref()).map(|r| {
+                    let info = TitleInfo {
+                        title: r.title,
+                        year: r.year,
+                        edition: r.edition,
+                        imdb: None,
+                        pattern: "release".to_string(),
+                    };
+                    (info, true)
+                })
+            })?;
+
+        let (title, split_edition) = edition::split(&info.title);
+        info.title = title;
+        info.edition = info
+            .edition
+            .or(split_edition)
+            .or_else(|| edition::from_name(parent))
+            .or_else(|| edition::from_name(file))
+            .or_else(|| Self::tag_edition(matroska));
+        Some((info, from_file_name))
     }
 
-    fn extract_title_year(str: impl AsRef<str>) -> Option<(String, i16)> {
-        RE.captures(str.as_ref()).map(|captures| {
-            let title = captures.get(1).unwrap().as_str().to_string();
-            let year: i16 = captures.get(2).unwrap().as_str().parse().unwrap();
-            (title, year)
-        })
+    /// Edition written into the file's tags, if any
+    fn tag_edition(matroska: &Matroska) -> Option<String> {
+        matroska
+            .tags
+            .iter()
+            .flat_map(|tag| &tag.simple)
+            .find(|simple| simple.name.eq_ignore_ascii_case("EDITION"))
+            .and_then(|simple| match &simple.value {
+                Some(TagValue::String(s)) if !s.trim().is_empty() => Some(s.trim().to_string()),
+                _ => None,
+            })
     }
 
-    fn mkvinfo_update(title: impl AsRef<str>, year: i16, path: &Path) {
-        let formatted_title = format!("title={} ({year})", title.as_ref());
+    /// Parse release info (source, group, etc.) from the file name,
+    /// falling back on the parent folder name
+    fn get_release(path: &Path) -> Option<Release> {
+        let stem = path.file_stem().and_then(|s| s.to_str());
+        let parent = path
+            .parent()
+            .and_then(|p| p.file_name())
+            .and_then(|s| s.to_str());
+
+        stem.and_then(Release::parse)
+            .filter(|r| r.source.is_some() || r.group.is_some())
+            .or_else(|| parent.and_then(Release::parse))
+    }
 
+    /// Write `title` into the file's metadata with mkvpropedit. Returns false,
+    /// writing nothing, if mkvpropedit is not installed.
+    fn mkvinfo_update(title: &str, path: &Path) -> error::Result<bool> {
         let output = std::process::Command::new("mkvpropedit")
             .arg(path.to_string_lossy().as_ref())
             .arg("--tags")
@@ -154,7 +451,7 @@ impl Movie {
             .arg("--edit")
             .arg("info")
             .arg("--set")
-            .arg(&formatted_title)
+            .arg(format!("title={title}"))
             .arg("--edit")
             .arg("track:s1")
             .arg("--set")
@@ -162,18 +459,26 @@ impl Movie {
             .output();
 
         match output {
-            Ok(o) => match o.status.success() {
-                true => println!(
-                    "Wrote title to metadata of file. [{}]",
-                    &formatted_title.split('=').last().unwrap_or_default()
-                ),
-                false => println!("FAILED TO UPDATE FILE TITLE: {:?}", path.file_name()),
-            },
-            Err(e) => println!("Failed to run mkvpropedit with error: {e}"),
+            Ok(o) if o.status.success() => Ok(true),
+            Ok(o) => Err(Error::io(path)(std::io::Error::other(format!(
+                "mkvpropedit could not write the title: {}",
+                String::from_utf8_lossy(&o.stdout).trim()
+            )))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(Error::io(path)(e)),
         }
     }
 
-    fn get_video_stream(track: &Track) -> VideoStream {
+    /// The first video track
+    pub(crate) fn video_track<'a>(matroska: &'a Matroska, path: &Path) -> error::Result<&'a Track> {
+        matroska
+            .tracks
+            .iter()
+            .find(|t| matches!(t.tracktype, Tracktype::Video))
+            .ok_or_else(|| Error::unusable(path, "no video track"))
+    }
+
+    pub(crate) fn get_video_stream(track: &Track, path: &Path) -> VideoStream {
         let codec = VideoCodec::from(track.codec_id.as_str());
         let bit_depth = match codec {
             VideoCodec::x265 | VideoCodec::AV1 => BitDepth::Bit10,
@@ -186,41 +491,74 @@ impl Movie {
         } else {
             Resolution::Err
         };
+        let hdr = Hdr::detect(&[
+            track.name.as_deref().unwrap_or_default(),
+            &path.file_stem().unwrap_or_default().to_string_lossy(),
+            &path
+                .parent()
+                .and_then(|p| p.file_name())
+                .unwrap_or_default()
+                .to_string_lossy(),
+        ]);
         VideoStream {
             resolution,
             codec,
             bit_depth,
+            hdr,
         }
     }
 
-    fn process_tracks(tracks: &[Track]) -> (AudioStream, SubtitleStream) {
+    pub(crate) fn process_tracks(tracks: &[Track]) -> (AudioStream, SubtitleStream) {
         let mut audio_info = AudioStream {
             codec: AudioCodec::from("NONE"),
             count: 0,
             channels: 0.0,
+            tracks: Vec::new(),
         };
 
         let mut sub_info = SubtitleStream {
             format: SubtitleFormat::from("NONE"),
             count: 0,
+            tracks: Vec::new(),
         };
 
-        for track in tracks[1..].iter() {
+        // Matroska defaults to english when no language is set
+        let language = |track: &Track| {
+            track
+                .language
+                .as_ref()
+                .map_or("eng".to_string(), |l| l.to_string())
+        };
+
+        for track in tracks {
             match track.tracktype {
                 Tracktype::Audio => {
                     audio_info.count += 1;
+                    let channels = match &track.settings {
+                        Audio(audio) => Self::map_audio_channels(audio.channels),
+                        _ => 0.0,
+                    };
                     if audio_info.count == 1 {
                         audio_info.codec = AudioCodec::from(track.codec_id.as_str());
-                        if let Audio(audio) = &track.settings {
-                            audio_info.channels = Self::map_audio_channels(audio.channels);
-                        }
+                        audio_info.channels = channels;
                     }
+                    audio_info.tracks.push(AudioTrack {
+                        codec: AudioCodec::from(track.codec_id.as_str()),
+                        channels,
+                        language: language(track),
+                    });
                 }
                 Tracktype::Subtitle => {
                     sub_info.count += 1;
                     if sub_info.count == 1 {
                         sub_info.format = SubtitleFormat::from(track.codec_id.as_str());
                     }
+                    sub_info.tracks.push(SubtitleTrack {
+                        format: SubtitleFormat::from(track.codec_id.as_str()),
+                        language: language(track),
+                        forced: track.forced,
+                        file: None,
+                    });
                 }
                 _ => (),
             }
@@ -245,24 +583,49 @@ impl Display for Movie {
     fn fmt(&self, f: &mut Formatter) -> Result {
         write!(
             f,
-            "{} ({}) [{:x}]\n\t{} | {:.2} GB\n\tVideo: {} | {}\n\tAudio: {} | ({} tracks) | {}\n\tSubs:  {} ({} subs)\n",
-            self.title,
-            self.year,
+            "{} [{:x}]{}\n\t{} | {:.2} GB{}\n\tVideo: {} | {} | {}\n\tAudio: {} | ({} tracks) | {}\n\tSubs:  {} ({} subs)\n{}",
+            self.display_name(),
             self.hash,
+            match &self.pattern {
+                Some(p) => format!(" <{p}>"),
+                None => String::new(),
+            },
             self.duration,
             self.size,
+            match self.parts.len() {
+                0 => String::new(),
+                n => format!(" | {n} parts"),
+            },
             self.video.resolution,
             self.video.codec,
+            self.video.hdr,
             self.audio.codec,
             self.audio.channels,
             self.audio.count,
             self.subs.format,
-            self.subs.count
+            self.subs.count,
+            match (&self.source, &self.group) {
+                (None, None) => String::new(),
+                (src, grp) => format!(
+                    "\tRelease: {} | {}\n",
+                    src.as_ref().map(|s| s.to_string()).unwrap_or_default(),
+                    grp.as_deref().unwrap_or_default()
+                ),
+            }
         )
     }
 }
 
 impl Movie {
+    /// `Title (Year)`, followed by the edition if there is one
+    pub fn display_name(&self) -> String {
+        match &self.edition {
+            Some(e) => format!("{} ({}) {{{e}}}", self.title, self.year),
+            None => format!("{} ({})", self.title, self.year),
+        }
+    }
+
+    /// The movie as a CSV row for the stats tables and `export csv`
     pub fn make_lines(&self) -> Cow<'_, str> {
         let rating = match self.rating.as_ref() {
             Some(s) => s.as_str(),
@@ -270,7 +633,7 @@ impl Movie {
         };
 
         Cow::from(format!(
-            "\"{}\",{},{},{},{:.2},{},{},{},{},{},{},{:x},{},{}",
+            "\"{}\",{},{},{},{:.2},{},{},{},{},{},{},{:x},{},{},{},{},{},\"{}\",{},\"{}\",{},\"{}\"",
             &self.title,
             &self.year,
             rating,
@@ -284,7 +647,73 @@ impl Movie {
             &self.subs.format,
             &self.hash,
             &self.audio.count,
-            &self.subs.count
+            &self.subs.count,
+            self.source.as_ref().map(|s| s.to_string()).unwrap_or_default(),
+            self.group.as_deref().unwrap_or_default(),
+            self.pattern.as_deref().unwrap_or_default(),
+            self.genres.join(", "),
+            &self.video.hdr,
+            self.edition.as_deref().unwrap_or_default(),
+            self.parts.len().max(1),
+            self.collection.as_deref().unwrap_or_default()
         ))
     }
 }
+
+#[cfg(test)]
+impl Movie {
+    /// A two hour 1080p movie, for tests that only care about its name, size and hash
+    pub(crate) fn fixture(title: &str, year: i16, size: f32, hash: u32) -> Self {
+        Movie {
+            title: title.to_string(),
+            year,
+            edition: None,
+            imdb: None,
+            pattern: None,
+            original_title: None,
+            tmdb: None,
+            genres: Vec::new(),
+            runtime: None,
+            user_rating: None,
+            rating: None,
+            size,
+            duration: "2h 00min".to_string(),
+            video: VideoStream {
+                resolution: Resolution::HD1080,
+                codec: VideoCodec::x265,
+                bit_depth: BitDepth::Bit10,
+                hdr: Hdr::SDR,
+            },
+            audio: AudioStream {
+                codec: AudioCodec::AC3,
+                channels: 6.0,
+                count: 1,
+                tracks: Vec::new(),
+            },
+            subs: SubtitleStream {
+                format: SubtitleFormat::SRT,
+                count: 0,
+                tracks: Vec::new(),
+            },
+            source: None,
+            group: None,
+            parts: Vec::new(),
+            collection: None,
+            hash,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_are_always_in_gb() {
+        let gb = 1024 * 1024 * 1024;
+        assert_eq!(Movie::make_gb(0), 0.0);
+        assert_eq!(Movie::make_gb(gb / 2), 0.5);
+        assert_eq!(Movie::make_gb(700 * 1024 * 1024), 700.0 / 1024.0);
+        assert_eq!(Movie::make_gb(30 * gb), 30.0);
+    }
+}
@@ -5,17 +5,27 @@ use rusqlite::{
 // use serde::Serialize;
 use std::fmt;
 
+/// Codec of an audio track
 #[derive(Debug)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum AudioCodec {
+    /// Advanced Audio Coding
     AAC,
+    /// Dolby Digital
     AC3,
+    /// Dolby TrueHD, with or without Atmos
     Atmos, // same as TrueHD
+    /// Dolby Digital Plus
     EAC3,
+    /// DTS and its variants
     DTS,
+    /// Free Lossless Audio Codec
     FLAC,
+    /// Opus
     OPUS,
+    /// Uncompressed PCM
     PCM,
+    /// Any other codec, by its codec id
     Other(String),
 }
 
@@ -33,7 +43,7 @@ impl From<&str> for AudioCodec {
             _ => {
                 let other = s
                     .split('_')
-                    .last()
+                    .next_back()
                     .unwrap_or("Err")
                     .split('/')
                     .next()
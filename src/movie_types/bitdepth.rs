@@ -4,10 +4,14 @@ use rusqlite::{
 };
 use std::fmt;
 
+/// Bits per colour channel of a video track
 #[derive(Debug)]
 pub enum BitDepth {
+    /// 10 bit
     Bit10,
+    /// 8 bit
     Bit8,
+    /// Any other depth
     Other(i8),
 }
 
@@ -0,0 +1,96 @@
+use rusqlite::{
+    types::{FromSql, FromSqlResult, ValueRef},
+    Result as RusqliteResult, ToSql,
+};
+use std::fmt;
+
+/// What an extra is, from its folder or file name
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtraKind {
+    /// Trailer
+    Trailer,
+    /// Sample clip
+    Sample,
+    /// Featurette
+    Featurette,
+    /// Behind the scenes footage
+    BehindTheScenes,
+    /// Deleted scene
+    DeletedScene,
+    /// Interview
+    Interview,
+    /// Scene
+    Scene,
+    /// Short film
+    Short,
+    /// Anything else next to a main feature
+    Other,
+}
+
+impl ExtraKind {
+    /// Kind for a Plex/Jellyfin extras folder (`Featurettes`, `Behind The Scenes`, ...)
+    pub fn from_folder(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "trailers" => Some(ExtraKind::Trailer),
+            "sample" | "samples" => Some(ExtraKind::Sample),
+            "featurettes" => Some(ExtraKind::Featurette),
+            "behind the scenes" => Some(ExtraKind::BehindTheScenes),
+            "deleted scenes" => Some(ExtraKind::DeletedScene),
+            "interviews" => Some(ExtraKind::Interview),
+            "scenes" => Some(ExtraKind::Scene),
+            "shorts" => Some(ExtraKind::Short),
+            "extras" | "other" => Some(ExtraKind::Other),
+            _ => None,
+        }
+    }
+
+    /// Kind for a Plex style file suffix, ie. `Movie-trailer`
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        match suffix.to_lowercase().as_str() {
+            "trailer" => Some(ExtraKind::Trailer),
+            "sample" => Some(ExtraKind::Sample),
+            "featurette" => Some(ExtraKind::Featurette),
+            "behindthescenes" => Some(ExtraKind::BehindTheScenes),
+            "deleted" => Some(ExtraKind::DeletedScene),
+            "interview" => Some(ExtraKind::Interview),
+            "scene" => Some(ExtraKind::Scene),
+            "short" => Some(ExtraKind::Short),
+            "other" => Some(ExtraKind::Other),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for ExtraKind {
+    fn from(s: &str) -> Self {
+        match s {
+            "Trailer" => ExtraKind::Trailer,
+            "Sample" => ExtraKind::Sample,
+            "Featurette" => ExtraKind::Featurette,
+            "BehindTheScenes" => ExtraKind::BehindTheScenes,
+            "DeletedScene" => ExtraKind::DeletedScene,
+            "Interview" => ExtraKind::Interview,
+            "Scene" => ExtraKind::Scene,
+            "Short" => ExtraKind::Short,
+            _ => ExtraKind::Other,
+        }
+    }
+}
+
+impl fmt::Display for ExtraKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl ToSql for ExtraKind {
+    fn to_sql(&self) -> RusqliteResult<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.to_string().into())
+    }
+}
+
+impl FromSql for ExtraKind {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str().map(ExtraKind::from)
+    }
+}
@@ -0,0 +1,90 @@
+use rusqlite::{
+    types::{FromSql, FromSqlResult, ValueRef},
+    Result as RusqliteResult, ToSql,
+};
+use std::fmt;
+
+/// HDR format of a video track
+#[derive(Debug, Default, Clone, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Hdr {
+    /// Standard dynamic range
+    #[default]
+    SDR,
+    /// HDR10
+    HDR10,
+    /// HDR10+
+    HDR10Plus,
+    /// Dolby Vision
+    DolbyVision,
+    /// Hybrid Log-Gamma
+    HLG,
+}
+
+impl Hdr {
+    /// Best guess from release style names, ie. track names and file names
+    pub fn detect(names: &[&str]) -> Self {
+        let tokens = names
+            .iter()
+            .flat_map(|n| n.split(|c: char| !c.is_alphanumeric() && c != '+'))
+            .map(|t| t.to_ascii_uppercase())
+            .collect::<Vec<_>>();
+        let has = |options: &[&str]| tokens.iter().any(|t| options.contains(&t.as_str()));
+
+        if has(&["DV", "DOVI", "DOLBYVISION"]) || names.iter().any(|n| n.contains("Dolby Vision")) {
+            Hdr::DolbyVision
+        } else if has(&["HDR10+", "HDR10PLUS"]) {
+            Hdr::HDR10Plus
+        } else if has(&["HLG"]) {
+            Hdr::HLG
+        } else if has(&["HDR", "HDR10"]) {
+            Hdr::HDR10
+        } else {
+            Hdr::SDR
+        }
+    }
+
+    /// Value used by Kodi's `hdrtype` stream detail
+    pub fn kodi_str(&self) -> &'static str {
+        match self {
+            Hdr::SDR => "",
+            Hdr::HDR10 | Hdr::HDR10Plus => "hdr10",
+            Hdr::DolbyVision => "dolbyvision",
+            Hdr::HLG => "hlg",
+        }
+    }
+}
+
+impl From<&str> for Hdr {
+    fn from(s: &str) -> Self {
+        match s {
+            "HDR10" => Hdr::HDR10,
+            "HDR10+" => Hdr::HDR10Plus,
+            "DV" => Hdr::DolbyVision,
+            "HLG" => Hdr::HLG,
+            _ => Hdr::SDR,
+        }
+    }
+}
+
+impl fmt::Display for Hdr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Hdr::HDR10Plus => write!(f, "HDR10+"),
+            Hdr::DolbyVision => write!(f, "DV"),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+impl ToSql for Hdr {
+    fn to_sql(&self) -> RusqliteResult<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.to_string().into())
+    }
+}
+
+impl FromSql for Hdr {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str().map(Hdr::from)
+    }
+}
@@ -1,6 +1,16 @@
+/// Audio codecs
 pub mod audio_codec;
+/// Video bit depths
 pub mod bitdepth;
+/// Kinds of extras
+pub mod extra_kind;
+/// HDR formats
+pub mod hdr;
+/// Resolution classes
 pub mod resolution;
+/// Release sources
+pub mod source;
+/// Subtitle formats
 pub mod sub_format;
+/// Video codecs
 pub mod video_codec;
-
@@ -8,13 +8,20 @@ use std::{
     str::FromStr,
 };
 
+/// Resolution class of a video track, from its height
 #[derive(Debug, Default)]
 pub enum Resolution {
+    /// Under 480 lines
     SD,
+    /// 480 to 720 lines
     HD720,
+    /// Up to 1080 lines
     HD1080,
+    /// Up to 2160 lines
     UHD4K,
+    /// Over 2160 lines
     UHD8K,
+    /// Not known
     #[default]
     Err,
 }
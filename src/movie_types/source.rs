@@ -0,0 +1,62 @@
+use rusqlite::{
+    types::{FromSql, FromSqlResult, ValueRef},
+    Result as RusqliteResult, ToSql,
+};
+use std::fmt;
+
+/// Where a release was taken from
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Source {
+    /// Untouched copy of a Blu-ray's streams
+    Remux,
+    /// Blu-ray encode
+    BluRay,
+    /// Untouched web download
+    WebDL,
+    /// Re-encoded web capture
+    WebRip,
+    /// TV recording
+    HDTV,
+    /// DVD
+    DVD,
+    /// Any other source, as named
+    Other(String),
+}
+
+impl From<&str> for Source {
+    fn from(s: &str) -> Self {
+        match s.to_ascii_uppercase().replace(['-', ' '], "").as_str() {
+            "REMUX" | "BDREMUX" => Source::Remux,
+            "BLURAY" | "BDRIP" | "BRRIP" | "BD" => Source::BluRay,
+            "WEBDL" | "WEB" => Source::WebDL,
+            "WEBRIP" => Source::WebRip,
+            "HDTV" => Source::HDTV,
+            "DVD" | "DVDRIP" | "DVD5" | "DVD9" => Source::DVD,
+            _ => Source::Other(s.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::WebDL => write!(f, "WEB-DL"),
+            Source::WebRip => write!(f, "WEBRip"),
+            Source::Other(s) => write!(f, "{}", s),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+impl ToSql for Source {
+    fn to_sql(&self) -> RusqliteResult<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(self.to_string().into())
+    }
+}
+
+impl FromSql for Source {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str().map(Source::from)
+    }
+}
@@ -5,14 +5,21 @@ use rusqlite::{
 // use serde::Serialize;
 use std::fmt;
 
-#[derive(Debug)]
+/// Format of a subtitle track
+#[derive(Debug, Clone)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum SubtitleFormat {
+    /// Advanced SubStation Alpha
     ASS,
+    /// Blu-ray bitmap subtitles
     PGS,
+    /// SubRip text
     SRT,
+    /// SubStation Alpha
     SSA,
+    /// DVD bitmap subtitles
     VOB,
+    /// Any other format, by its codec id
     Other(String),
 }
 
@@ -21,13 +28,13 @@ impl From<&str> for SubtitleFormat {
         match s {
             "ASS" | "S_TEXT/ASS" => SubtitleFormat::ASS,
             "PGS" | "S_HDMV/PGS" => SubtitleFormat::PGS,
-            "UTF8" | "S_TEXT/UTF8" => SubtitleFormat::SRT,
+            "SRT" | "UTF8" | "S_TEXT/UTF8" => SubtitleFormat::SRT,
             "SSA" | "S_TEXT/SSA" => SubtitleFormat::SSA,
             "VOB" | "S_VOBSUB" => SubtitleFormat::VOB,
             _ => {
                 let other = s
                     .split('_')
-                    .last()
+                    .next_back()
                     .unwrap_or("Err")
                     .split('/')
                     .next()
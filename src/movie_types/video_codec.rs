@@ -5,12 +5,17 @@ use rusqlite::{
 // use serde::Serialize;
 use std::fmt;
 
+/// Codec of a video track
 #[derive(Debug)]
 #[allow(non_camel_case_types)]
 pub enum VideoCodec {
+    /// H.264/AVC
     x264,
+    /// H.265/HEVC
     x265,
+    /// AV1
     AV1,
+    /// Any other codec, by its codec id
     Other(String),
 }
 
@@ -23,10 +28,10 @@ impl From<&str> for VideoCodec {
             _ => {
                 let other = s
                     .split('_')
-                    .last()
+                    .next_back()
                     .unwrap_or("Err")
                     .split('/')
-                    .last()
+                    .next_back()
                     .unwrap_or("Err");
                 VideoCodec::Other(other.to_string())
             }
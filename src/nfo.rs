@@ -0,0 +1,348 @@
+use crate::{
+    error::{self, Error},
+    movie::Movie,
+    movie_types::{audio_codec::AudioCodec, resolution::Resolution, video_codec::VideoCodec},
+};
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+/// First line after the xml declaration of every nfo file numov writes
+const NUMOV_MARKER: &str = "<!-- written by numov -->";
+
+/// When an existing nfo file may be replaced
+#[derive(Debug, Clone, Copy)]
+pub enum Overwrite {
+    /// Never replace an existing nfo
+    Never,
+    /// Only replace nfo files previously written by numov
+    Numov,
+    /// Always replace
+    Always,
+}
+
+/// Movie information read from a Kodi/Jellyfin style `.nfo` file
+#[derive(Debug, Default)]
+pub struct Nfo {
+    pub title: Option<String>,
+    pub original_title: Option<String>,
+    pub year: Option<i16>,
+    pub imdb: Option<String>,
+    pub tmdb: Option<String>,
+    pub genres: Vec<String>,
+    pub runtime: Option<u32>,
+    pub user_rating: Option<f32>,
+}
+
+impl Nfo {
+    /// Look for `movie.nfo`, then `<file name>.nfo`, next to the provided file
+    pub fn find(path: &Path) -> Option<PathBuf> {
+        let dir = path.parent()?;
+        [dir.join("movie.nfo"), path.with_extension("nfo")]
+            .into_iter()
+            .find(|p| p.is_file())
+    }
+
+    /// Read and parse the nfo file at `path`
+    pub fn read(path: impl AsRef<Path>) -> error::Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(Error::io(path))?;
+        Self::parse(&contents).ok_or_else(|| Error::unusable(path, "not a valid movie nfo"))
+    }
+
+    fn parse(contents: &str) -> Option<Self> {
+        // Some scrapers append a plain url after the xml document
+        let end = contents.find("</movie>")? + "</movie>".len();
+        let doc = roxmltree::Document::parse(&contents[..end]).ok()?;
+        let movie = doc.root_element();
+        if !movie.has_tag_name("movie") {
+            return None;
+        }
+
+        let text = |tag: &str| {
+            movie
+                .children()
+                .find(|n| n.has_tag_name(tag))
+                .and_then(|n| n.text())
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+        };
+
+        let unique_id = |kind: &str| {
+            movie
+                .children()
+                .filter(|n| n.has_tag_name("uniqueid"))
+                .find(|n| n.attribute("type") == Some(kind))
+                .and_then(|n| n.text())
+                .map(|t| t.trim().to_string())
+        };
+
+        let year = text("year")
+            .or_else(|| text("premiered"))
+            .and_then(|y| y.get(..4).and_then(|y| y.parse().ok()));
+
+        let imdb = unique_id("imdb")
+            .or_else(|| text("imdbid"))
+            .or_else(|| text("id").filter(|id| id.starts_with("tt")));
+
+        let genres = movie
+            .children()
+            .filter(|n| n.has_tag_name("genre"))
+            .filter_map(|n| n.text())
+            .flat_map(|g| g.split(" / "))
+            .map(|g| g.trim().to_string())
+            .filter(|g| !g.is_empty())
+            .collect();
+
+        Some(Nfo {
+            title: text("title"),
+            original_title: text("originaltitle"),
+            year,
+            imdb,
+            tmdb: unique_id("tmdb").or_else(|| text("tmdbid")),
+            genres,
+            runtime: text("runtime").and_then(|r| r.parse().ok()),
+            user_rating: text("userrating").and_then(|r| r.parse().ok()),
+        })
+    }
+}
+
+// ==================
+// WRITING
+// ==================
+impl Nfo {
+    /// Whether the nfo at `path` may be (over)written under the given policy
+    pub fn may_write(path: &Path, policy: Overwrite) -> bool {
+        match (path.exists(), policy) {
+            (false, _) | (true, Overwrite::Always) => true,
+            (true, Overwrite::Never) => false,
+            (true, Overwrite::Numov) => std::fs::read_to_string(path)
+                .is_ok_and(|contents| contents.contains(NUMOV_MARKER)),
+        }
+    }
+
+    /// Build a Kodi/Jellyfin compatible nfo document from a movie
+    pub fn render(movie: &Movie) -> String {
+        let mut xml = String::new();
+        let _ = writeln!(xml, "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\" ?>");
+        let _ = writeln!(xml, "{NUMOV_MARKER}\n<movie>");
+
+        let mut fields = vec![("title", movie.title.clone())];
+        if let Some(original) = &movie.original_title {
+            fields.push(("originaltitle", original.clone()));
+        }
+        fields.push(("year", movie.year.to_string()));
+        fields.push(("runtime", movie.runtime_minutes().to_string()));
+        if let Some(rating) = movie.rating.as_deref().and_then(Self::userrating) {
+            fields.push(("userrating", rating.to_string()));
+        }
+        fields.extend(movie.genres.iter().map(|g| ("genre", g.clone())));
+        write_fields(&mut xml, 2, &fields);
+
+        if let Some(imdb) = &movie.imdb {
+            let _ = writeln!(xml, "  <uniqueid type=\"imdb\" default=\"true\">{}</uniqueid>", escape(imdb));
+        }
+        if let Some(tmdb) = &movie.tmdb {
+            let _ = writeln!(xml, "  <uniqueid type=\"tmdb\">{}</uniqueid>", escape(tmdb));
+        }
+
+        // Only the resolution class is known, so width and height are nominal
+        let (width, height) = match movie.video.resolution {
+            Resolution::SD => (720, 480),
+            Resolution::HD720 => (1280, 720),
+            Resolution::HD1080 => (1920, 1080),
+            Resolution::UHD4K => (3840, 2160),
+            Resolution::UHD8K => (7680, 4320),
+            Resolution::Err => (0, 0),
+        };
+
+        let mut streams = vec![(
+            "video",
+            vec![
+                ("codec", Self::video_codec(&movie.video.codec)),
+                ("width", width.to_string()),
+                ("height", height.to_string()),
+                ("hdrtype", movie.video.hdr.kodi_str().to_string()),
+                ("durationinseconds", (movie.runtime_minutes() * 60).to_string()),
+            ],
+        )];
+
+        match movie.audio.tracks.is_empty() {
+            // Rows read from older databases only know the first track
+            true if movie.audio.count > 0 => streams.push((
+                "audio",
+                vec![
+                    ("codec", Self::audio_codec(&movie.audio.codec)),
+                    ("channels", Self::channel_count(movie.audio.channels).to_string()),
+                ],
+            )),
+            _ => streams.extend(movie.audio.tracks.iter().map(|track| {
+                (
+                    "audio",
+                    vec![
+                        ("codec", Self::audio_codec(&track.codec)),
+                        ("language", track.language.clone()),
+                        ("channels", Self::channel_count(track.channels).to_string()),
+                    ],
+                )
+            })),
+        }
+
+        streams.extend(
+            movie
+                .subs
+                .tracks
+                .iter()
+                .map(|track| ("subtitle", vec![("language", track.language.clone())])),
+        );
+
+        let _ = writeln!(xml, "  <fileinfo>\n    <streamdetails>");
+        for (name, fields) in streams {
+            let _ = writeln!(xml, "      <{name}>");
+            write_fields(&mut xml, 8, &fields);
+            let _ = writeln!(xml, "      </{name}>");
+        }
+        let _ = writeln!(xml, "    </streamdetails>\n  </fileinfo>\n</movie>");
+
+        xml
+    }
+
+    /// Convert a letterboxd star rating (ie. `★★★½`) to a 1-10 score
+    pub(crate) fn userrating(stars: &str) -> Option<u8> {
+        let score = stars.chars().fold(0, |acc, c| match c {
+            '★' => acc + 2,
+            '½' => acc + 1,
+            _ => acc,
+        });
+        (score > 0).then_some(score)
+    }
+
+    fn video_codec(codec: &VideoCodec) -> String {
+        match codec {
+            VideoCodec::x264 => "h264".to_string(),
+            VideoCodec::x265 => "hevc".to_string(),
+            VideoCodec::AV1 => "av1".to_string(),
+            VideoCodec::Other(s) => s.to_lowercase(),
+        }
+    }
+
+    fn audio_codec(codec: &AudioCodec) -> String {
+        match codec {
+            AudioCodec::Atmos => "truehd".to_string(),
+            c => c.to_string().to_lowercase(),
+        }
+    }
+
+    fn channel_count(channels: f32) -> u8 {
+        channels.trunc() as u8 + u8::from(channels.fract() > 0.0)
+    }
+}
+
+fn write_fields(xml: &mut String, indent: usize, fields: &[(&str, String)]) {
+    for (name, value) in fields {
+        let _ = writeln!(xml, "{:indent$}<{name}>{}</{name}>", "", escape(value));
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kodi_nfo_with_a_trailing_url() {
+        let nfo = Nfo::parse(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes" ?>
+<movie>
+  <title>Mission: Impossible</title>
+  <originaltitle> </originaltitle>
+  <premiered>1996-05-22</premiered>
+  <uniqueid type="imdb" default="true">tt0117060</uniqueid>
+  <uniqueid type="tmdb">954</uniqueid>
+  <genre>Action / Thriller</genre>
+  <genre>Adventure</genre>
+  <runtime>110</runtime>
+</movie>
+https://www.themoviedb.org/movie/954"#,
+        )
+        .unwrap();
+
+        assert_eq!(nfo.title.as_deref(), Some("Mission: Impossible"));
+        assert_eq!(nfo.original_title, None, "blank tags count as missing");
+        assert_eq!(nfo.year, Some(1996));
+        assert_eq!((nfo.imdb.as_deref(), nfo.tmdb.as_deref()), (Some("tt0117060"), Some("954")));
+        assert_eq!(nfo.genres, ["Action", "Thriller", "Adventure"]);
+        assert_eq!((nfo.runtime, nfo.user_rating), (Some(110), None));
+    }
+
+    #[test]
+    fn missing_fields_are_none() {
+        let nfo = Nfo::parse("<movie><title>Heat</title><year>soon</year></movie>").unwrap();
+        assert_eq!(nfo.title.as_deref(), Some("Heat"));
+        assert_eq!((nfo.year, nfo.imdb, nfo.runtime), (None, None, None));
+        assert!(nfo.genres.is_empty());
+    }
+
+    #[test]
+    fn invalid_xml_is_not_an_nfo() {
+        for contents in [
+            "",
+            "https://www.imdb.com/title/tt0113277/",
+            "<movie><title>Heat</title>",
+            "<movie><title>Heat</movie>",
+            "<tvshow><title>Heat</title></tvshow><movie></movie>",
+        ] {
+            assert!(Nfo::parse(contents).is_none(), "{contents:?}");
+        }
+    }
+
+    #[test]
+    fn rendered_nfo_reads_back_as_written() {
+        let mut movie = Movie::fixture("Mission: Impossible", 1996, 8.0, 1);
+        movie.genres = vec!["Action & Adventure".to_string()];
+        movie.rating = Some("★★★½".to_string());
+        let xml = Nfo::render(&movie);
+
+        assert!(xml.contains("<genre>Action &amp; Adventure</genre>"), "{xml}");
+        let nfo = Nfo::parse(&xml).unwrap();
+        assert_eq!(nfo.title.as_deref(), Some("Mission: Impossible"), "the title is not sanitized");
+        assert_eq!(nfo.year, Some(1996));
+        assert_eq!(nfo.genres, ["Action & Adventure"]);
+        assert_eq!(nfo.user_rating, Some(7.0));
+    }
+
+    #[test]
+    fn only_numov_files_are_replaced_under_the_numov_policy() {
+        let dir = std::env::temp_dir().join(format!("numov-nfo-policy-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (ours, theirs, none) = (dir.join("ours.nfo"), dir.join("theirs.nfo"), dir.join("none.nfo"));
+        std::fs::write(&ours, Nfo::render(&Movie::fixture("Heat", 1995, 8.0, 1))).unwrap();
+        std::fs::write(&theirs, "<movie><title>Heat</title></movie>").unwrap();
+
+        let may = |path: &Path| {
+            [Overwrite::Never, Overwrite::Numov, Overwrite::Always].map(|policy| Nfo::may_write(path, policy))
+        };
+        let found = [may(&ours), may(&theirs), may(&none)];
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found, [[false, true, true], [false, false, true], [true, true, true]]);
+    }
+
+    #[test]
+    fn reading_an_invalid_nfo_names_the_file() {
+        let path = std::env::temp_dir().join(format!("numov-nfo-{}.nfo", std::process::id()));
+        std::fs::write(&path, "<movie><title>Heat</title>").unwrap();
+        let read = Nfo::read(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(read, Err(Error::Unusable { path: p, .. }) if p == path));
+        assert!(matches!(Nfo::read(&path), Err(Error::Io { .. })), "a missing file is an I/O error");
+    }
+}
@@ -0,0 +1,148 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::sync::LazyLock;
+
+/// Patterns used when none of the user defined patterns match
+static DEFAULT_PATTERNS: LazyLock<Vec<NamePattern>> = LazyLock::new(|| {
+    let re = r"(?P<title>.*) \((?P<year>\d{4})\)";
+    vec![
+        NamePattern::new("metadata", Target::Metadata, re).unwrap(),
+        NamePattern::new("folder", Target::Folder, re).unwrap(),
+    ]
+});
+
+/// Which name a pattern is applied to
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Target {
+    Metadata,
+    Folder,
+    File,
+}
+
+/// A named regex with `title` and `year` captures, and optionally `edition` and `imdb`
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "RawPattern")]
+pub struct NamePattern {
+    pub name: String,
+    pub target: Target,
+    pub regex: Regex,
+}
+
+#[derive(Deserialize)]
+struct RawPattern {
+    name: String,
+    target: Target,
+    regex: String,
+}
+
+impl TryFrom<RawPattern> for NamePattern {
+    type Error = String;
+
+    fn try_from(raw: RawPattern) -> Result<Self, Self::Error> {
+        NamePattern::new(&raw.name, raw.target, &raw.regex)
+    }
+}
+
+/// Title information extracted by a pattern
+#[derive(Debug)]
+pub struct TitleInfo {
+    pub title: String,
+    pub year: i16,
+    pub edition: Option<String>,
+    pub imdb: Option<String>,
+    pub pattern: String,
+}
+
+impl NamePattern {
+    pub fn new(name: &str, target: Target, regex: &str) -> Result<Self, String> {
+        let regex = Regex::new(regex).map_err(|e| format!("Pattern '{name}': {e}"))?;
+
+        let names = regex.capture_names().flatten().collect::<Vec<_>>();
+        if !names.contains(&"title") || !names.contains(&"year") {
+            return Err(format!(
+                "Pattern '{name}' must contain both (?P<title>..) and (?P<year>..) captures"
+            ));
+        }
+
+        Ok(NamePattern {
+            name: name.to_string(),
+            target,
+            regex,
+        })
+    }
+
+    pub fn defaults() -> &'static [NamePattern] {
+        &DEFAULT_PATTERNS
+    }
+
+    pub fn captures(&self, str: impl AsRef<str>) -> Option<TitleInfo> {
+        let captures = self.regex.captures(str.as_ref())?;
+        let get = |name: &str| {
+            captures
+                .name(name)
+                .map(|m| m.as_str().trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+
+        Some(TitleInfo {
+            title: get("title")?,
+            year: get("year")?.parse().ok()?,
+            edition: get("edition"),
+            imdb: get("imdb").map(|id| match id.starts_with("tt") {
+                true => id,
+                false => format!("tt{id}"),
+            }),
+            pattern: self.name.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_need_title_and_year_captures() {
+        let cases = [
+            (r"(?P<title>.*) \((?P<year>\d{4})\)", true),
+            (r"(?P<title>.*) (?P<year>\d{4}) (?P<edition>.*)", true),
+            (r"(?P<title>.*) \(\d{4}\)", false),
+            (r"(?P<year>\d{4})", false),
+            (r"(?P<title>.*) \((?P<year>\d{4}", false),
+        ];
+        for (regex, valid) in cases {
+            assert_eq!(NamePattern::new("test", Target::Folder, regex).is_ok(), valid, "{regex}");
+        }
+    }
+
+    #[test]
+    fn captures_title_info() {
+        let imdb = r"(?P<title>.+) \[(?P<year>\d{4})\](?: \{imdb-(?P<imdb>\w+)\})?(?: - (?P<edition>.*))?";
+        // regex, name, title, year, edition, imdb
+        let cases = [
+            (r"(?P<title>.*) \((?P<year>\d{4})\)", "Heat (1995)", Some(("Heat", 1995, None, None))),
+            (imdb, "Heat [1995]", Some(("Heat", 1995, None, None))),
+            (imdb, "Heat [1995] {imdb-tt0113277}", Some(("Heat", 1995, None, Some("tt0113277")))),
+            (imdb, "Heat [1995] {imdb-0113277}", Some(("Heat", 1995, None, Some("tt0113277")))),
+            (imdb, "Alien [1979] - Director's Cut", Some(("Alien", 1979, Some("Director's Cut"), None))),
+            (imdb, "Alien (1979)", None),
+            (r"(?P<title>.*) (?P<year>\w+)", "Heat year", None),
+            (r"(?P<title>.*)\((?P<year>\d{4})\)", " (1995)", None),
+        ];
+        for (regex, name, expected) in cases {
+            let pattern = NamePattern::new("test", Target::Folder, regex).unwrap();
+            let found = pattern.captures(name);
+            let found = found.as_ref().map(|t| (t.title.as_str(), t.year, t.edition.as_deref(), t.imdb.as_deref()));
+            assert_eq!(found, expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn defaults_read_metadata_then_folder() {
+        let targets = NamePattern::defaults().iter().map(|p| p.target).collect::<Vec<_>>();
+        assert_eq!(targets, [Target::Metadata, Target::Folder]);
+        let info = NamePattern::defaults()[1].captures("Blade Runner (1982)").unwrap();
+        assert_eq!((info.title.as_str(), info.year, info.pattern.as_str()), ("Blade Runner", 1982, "folder"));
+    }
+}
@@ -0,0 +1,376 @@
+use crate::error::{self, Error};
+use matroska::{ChapterEdition, Info, Matroska, MatroskaError, Parseable, Tag, Track};
+use std::{
+    fs::File,
+    io::{self, BufReader, Cursor, Read, Seek, SeekFrom},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+pub(crate) const EBML: u32 = 0x1A45DFA3;
+pub(crate) const SEGMENT: u32 = 0x18538067;
+pub(crate) const SEEKHEAD: u32 = 0x114D9B74;
+const SEEK: u32 = 0x4DBB;
+const SEEK_ID: u32 = 0x53AB;
+const SEEK_POSITION: u32 = 0x53AC;
+pub(crate) const INFO: u32 = 0x1549A966;
+pub(crate) const TRACKS: u32 = 0x1654AE6B;
+pub(crate) const TAGS: u32 = 0x1254C367;
+pub(crate) const CHAPTERS: u32 = 0x1043A770;
+pub(crate) const CLUSTER: u32 = 0x1F43B675;
+
+/// What a header-only read of a Matroska file found
+#[derive(Debug)]
+pub struct Probe {
+    /// Info, Tracks, Tags and Chapters; attachments are never read
+    pub matroska: Matroska,
+    /// Bytes read to get here, including skipped seeks
+    pub bytes_read: u64,
+}
+
+/// Reads a Matroska file's headers without touching its clusters or attachments.
+/// The segment is walked up to the first cluster, then the SeekHead is followed
+/// to whatever of Info, Tracks, Tags and Chapters is still missing. Each element
+/// is read in a single read, and no more than `budget` bytes are read in total.
+pub fn open(path: &Path, budget: u64) -> error::Result<Probe> {
+    let counted = Counted {
+        inner: File::open(path).map_err(Error::io(path))?,
+        read: 0,
+    };
+    let mut r = Reader {
+        inner: BufReader::with_capacity(4096, counted),
+        budget,
+    };
+    Ok(Probe {
+        matroska: read(&mut r).map_err(Error::matroska(path))?,
+        bytes_read: r.inner.get_ref().read,
+    })
+}
+
+/// Bytes read and time taken by the header-only probe and by `Matroska::open`
+pub fn compare(path: &Path, budget: u64) -> error::Result<[(u64, Duration); 2]> {
+    let t = Instant::now();
+    let probe = open(path, budget)?;
+    let probe_time = t.elapsed();
+
+    let t = Instant::now();
+    let mut counted = Counted {
+        inner: File::open(path).map_err(Error::io(path))?,
+        read: 0,
+    };
+    Matroska::open(&mut counted).map_err(Error::matroska(path))?;
+    let full_time = t.elapsed();
+
+    Ok([(probe.bytes_read, probe_time), (counted.read, full_time)])
+}
+
+/// Counts the bytes actually read from the file
+struct Counted<R> {
+    inner: R,
+    read: u64,
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.read += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for Counted<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+struct Reader<R> {
+    inner: BufReader<Counted<R>>,
+    budget: u64,
+}
+
+impl<R: Read> Read for Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for Reader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+
+    // The default goes through `seek`, which would throw the buffer away
+    fn stream_position(&mut self) -> io::Result<u64> {
+        self.inner.stream_position()
+    }
+}
+
+impl<R: Read + Seek> Reader<R> {
+    /// Move to an absolute position, keeping the buffer when it already holds it
+    fn goto(&mut self, pos: u64) -> io::Result<()> {
+        let here = self.inner.stream_position()?;
+        self.inner.seek_relative(pos as i64 - here as i64)
+    }
+
+    /// Read an element's body in one go, unless that would go over the budget
+    fn body(&mut self, size: u64) -> Result<Vec<u8>, MatroskaError> {
+        if self.inner.get_ref().read.saturating_add(size) > self.budget {
+            let e = format!("read budget of {} KB exceeded", self.budget / 1024);
+            return Err(io::Error::other(e).into());
+        }
+        let mut buf = vec![0; size as usize];
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+fn read<R: Read + Seek>(r: &mut Reader<R>) -> Result<Matroska, MatroskaError> {
+    let (id, size, _) = element_header(r)?;
+    if id != EBML {
+        return Err(MatroskaError::InvalidID);
+    }
+    r.inner.seek_relative(size as i64)?;
+
+    let (mut id, mut size, _) = element_header(r)?;
+    while id != SEGMENT {
+        r.inner.seek_relative(size as i64)?;
+        (id, size, _) = element_header(r)?;
+    }
+    let segment_start = r.stream_position()?;
+
+    let mut info = None;
+    let mut tracks = None;
+    let mut tags = None;
+    let mut chapters = None;
+    let mut seeks = Vec::new();
+
+    // Walk the top level elements up to the first cluster
+    let mut pos = segment_start;
+    while pos < segment_start.saturating_add(size) {
+        let Ok((id, size, len)) = element_header(r) else {
+            break;
+        };
+        if id == CLUSTER || size == u64::MAX {
+            break;
+        }
+        match id {
+            SEEKHEAD => seeks.extend(seek_entries(&r.body(size)?, segment_start)),
+            INFO => info = Some(Info::parse(&mut Cursor::new(r.body(size)?), size)?),
+            TRACKS => tracks = Some(r.body(size)?),
+            TAGS => tags = r.body(size).ok(),
+            CHAPTERS => chapters = r.body(size).ok(),
+            _ => {}
+        }
+        pos += len + size;
+        r.goto(pos)?;
+    }
+
+    // Jump to whatever the SeekHead points at that was not before the first cluster.
+    // A SeekHead may point to a second one, usually at the end of the file.
+    let mut i = 0;
+    while i < seeks.len() {
+        let (id, at) = seeks[i];
+        i += 1;
+        let missing = match id {
+            INFO => info.is_none(),
+            TRACKS => tracks.is_none(),
+            TAGS => tags.is_none(),
+            CHAPTERS => chapters.is_none(),
+            SEEKHEAD => at > pos,
+            _ => false,
+        };
+        if !missing {
+            continue;
+        }
+        // A cut short file may point past its end; what was found so far still counts
+        if r.goto(at).is_err() {
+            continue;
+        }
+        let size = match element_header(r) {
+            Ok((found, size, _)) if found == id => size,
+            _ => continue,
+        };
+        match id {
+            INFO => info = Some(Info::parse(&mut Cursor::new(r.body(size)?), size)?),
+            TRACKS => tracks = Some(r.body(size)?),
+            // Tags and chapters are nice to have, so a budget overrun only skips them
+            TAGS => tags = r.body(size).ok(),
+            CHAPTERS => chapters = r.body(size).ok(),
+            SEEKHEAD => {
+                let more = seek_entries(&r.body(size)?, segment_start);
+                seeks.extend(more.into_iter().filter(|(id, _)| *id != SEEKHEAD));
+            }
+            _ => {}
+        }
+    }
+
+    let tracks = tracks.ok_or(MatroskaError::InvalidID)?;
+    Ok(Matroska {
+        info: info.ok_or(MatroskaError::InvalidID)?,
+        tracks: Track::parse(&mut Cursor::new(&tracks), tracks.len() as u64)?,
+        attachments: Vec::new(),
+        chapters: match chapters {
+            Some(b) => ChapterEdition::parse(&mut Cursor::new(&b), b.len() as u64).unwrap_or_default(),
+            None => Vec::new(),
+        },
+        tags: match tags {
+            Some(b) => Tag::parse(&mut Cursor::new(&b), b.len() as u64).unwrap_or_default(),
+            None => Vec::new(),
+        },
+    })
+}
+
+/// Element ID, data size (`u64::MAX` if unknown) and header length
+pub(crate) fn element_header<R: Read>(r: &mut R) -> Result<(u32, u64, u64), MatroskaError> {
+    let (id, id_len) = vint(r, true)?;
+    let (size, size_len) = vint(r, false)?;
+    Ok((id as u32, size, id_len + size_len))
+}
+
+/// Read a variable length integer. IDs keep their length marker, sizes do not,
+/// and a size with all value bits set means "unknown".
+fn vint<R: Read>(r: &mut R, keep_marker: bool) -> Result<(u64, u64), MatroskaError> {
+    let mut first = [0];
+    r.read_exact(&mut first)?;
+    let len = first[0].leading_zeros() as u64 + 1;
+    if len > 8 || (keep_marker && len > 4) {
+        return Err(MatroskaError::InvalidSize);
+    }
+
+    let marker = 1u64 << (7 * len);
+    let mut value = first[0] as u64;
+    let mut rest = [0; 7];
+    r.read_exact(&mut rest[..len as usize - 1])?;
+    for byte in &rest[..len as usize - 1] {
+        value = (value << 8) | *byte as u64;
+    }
+
+    match keep_marker {
+        true => Ok((value, len)),
+        false if value == (marker << 1) - 1 => Ok((u64::MAX, len)),
+        false => Ok((value & (marker - 1), len)),
+    }
+}
+
+/// Child elements of an element's body
+pub(crate) fn children(mut buf: &[u8]) -> impl Iterator<Item = (u32, &[u8])> {
+    std::iter::from_fn(move || {
+        let mut cursor = Cursor::new(buf);
+        let (id, size, len) = element_header(&mut cursor).ok()?;
+        let end = (len as usize).checked_add(usize::try_from(size).ok()?)?;
+        let data = buf.get(len as usize..end)?;
+        buf = &buf[end..];
+        Some((id, data))
+    })
+}
+
+pub(crate) fn uint(data: &[u8]) -> u64 {
+    data.iter().fold(0, |acc, b| (acc << 8) | *b as u64)
+}
+
+/// (element ID, absolute position) of each SeekHead entry
+pub(crate) fn seek_entries(buf: &[u8], segment_start: u64) -> Vec<(u32, u64)> {
+    children(buf)
+        .filter(|(id, _)| *id == SEEK)
+        .filter_map(|(_, seek)| {
+            let mut id = None;
+            let mut position = None;
+            for (child, data) in children(seek) {
+                match child {
+                    SEEK_ID => id = Some(uint(data) as u32),
+                    SEEK_POSITION => position = Some(segment_start + uint(data)),
+                    _ => {}
+                }
+            }
+            Some((id?, position?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ebml::{element, uint};
+
+    /// A file with one video track, and a tag whose value is `tag_len` bytes long
+    fn file(tag_len: usize) -> Vec<u8> {
+        let info = [uint(0x2AD7B1, 1_000_000), element(0x4489, &7_200_000f64.to_be_bytes())].concat();
+        let track = [uint(0xD7, 1), uint(0x73C5, 1), uint(0x83, 1), element(0x86, b"V_AV1")].concat();
+        let simple_tag = [element(0x45A3, b"ENCODER"), element(0x4487, &vec![b'x'; tag_len])].concat();
+        let tag = [element(0x63C0, &[]), element(0x67C8, &simple_tag)].concat();
+        let segment = [
+            element(INFO, &info),
+            element(TRACKS, &element(0xAE, &track)),
+            element(TAGS, &element(0x7373, &tag)),
+            element(CLUSTER, &uint(0xE7, 0)),
+        ]
+        .concat();
+        [element(EBML, &element(0x4282, b"matroska")), element(SEGMENT, &segment)].concat()
+    }
+
+    fn probe(bytes: &[u8], budget: u64) -> Result<Matroska, MatroskaError> {
+        let counted = Counted {
+            inner: Cursor::new(bytes),
+            read: 0,
+        };
+        read(&mut Reader {
+            inner: BufReader::with_capacity(4096, counted),
+            budget,
+        })
+    }
+
+    #[test]
+    fn vint_reads_ids_and_sizes() {
+        /// Bytes, whether the marker bit is kept, and the value and length read
+        type Case = (&'static [u8], bool, Option<(u64, u64)>);
+        let cases: [Case; 9] = [
+            (&[0x81], false, Some((1, 1))),
+            (&[0x40, 0x02], false, Some((2, 2))),
+            (&[0x01, 0, 0, 0, 0, 0, 0, 5], false, Some((5, 8))),
+            (&[0x1A, 0x45, 0xDF, 0xA3], true, Some((0x1A45DFA3, 4))),
+            (&[0xFF], false, Some((u64::MAX, 1))),
+            (&[0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF], false, Some((u64::MAX, 8))),
+            (&[0x08, 0, 0, 0, 0], true, None),
+            (&[0x00], false, None),
+            (&[0x40], false, None),
+        ];
+        for (bytes, keep_marker, expected) in cases {
+            let found = vint(&mut Cursor::new(bytes), keep_marker).ok();
+            assert_eq!(found, expected, "{bytes:02x?}");
+        }
+    }
+
+    #[test]
+    fn children_stop_at_a_cut_short_element() {
+        /// Element body and the children found in it
+        type Case = (&'static [u8], &'static [(u32, &'static [u8])]);
+        let cases: [Case; 5] = [
+            (&[], &[]),
+            (&[0xE7, 0x81, 0x05], &[(0xE7, &[0x05])]),
+            (&[0xE7, 0x81, 0x05, 0xA3, 0x82, 0x01, 0x02], &[(0xE7, &[0x05]), (0xA3, &[0x01, 0x02])]),
+            (&[0xE7, 0x81, 0x05, 0xA3, 0x85, 0x01], &[(0xE7, &[0x05])]),
+            (&[0xE7, 0xFF, 0x05], &[]),
+        ];
+        for (buf, expected) in cases {
+            assert_eq!(children(buf).collect::<Vec<_>>(), expected, "{buf:02x?}");
+        }
+    }
+
+    #[test]
+    fn headers_over_the_budget_are_an_error() {
+        let e = probe(&file(0), 64).unwrap_err();
+        assert!(e.to_string().contains("read budget of 0 KB exceeded"), "{e}");
+    }
+
+    #[test]
+    fn tags_over_the_budget_are_skipped() {
+        let bytes = file(16 * 1024);
+        assert_eq!(probe(&bytes, 1 << 20).unwrap().tags.len(), 1);
+
+        let mkv = probe(&bytes, 8 * 1024).unwrap();
+        assert!(mkv.tags.is_empty());
+        assert_eq!(mkv.tracks.len(), 1);
+        assert_eq!(mkv.info.duration, Some(Duration::from_secs(7200)));
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{
+    edition::{self, EDITION_RE},
+    movie_types::{resolution::Resolution, source::Source},
+};
+use regex::Regex;
+use std::sync::LazyLock;
+
+static GROUP_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"-(?P<group>[A-Za-z0-9]+)(?:\[[^\]]*\])?$").unwrap());
+
+/// Information pulled from a scene/release style name,
+/// ie. `Movie.Title.2019.1080p.BluRay.x265-GROUP`
+#[derive(Debug, Default)]
+pub struct Release {
+    pub title: String,
+    pub year: i16,
+    pub edition: Option<String>,
+    pub source: Option<Source>,
+    pub resolution: Option<Resolution>,
+    pub group: Option<String>,
+}
+
+impl Release {
+    /// Parse a release name (folder name or file stem). Returns `None`
+    /// if no title/year pair can be found.
+    pub fn parse(name: impl AsRef<str>) -> Option<Self> {
+        let name = name.as_ref().trim();
+        let normalized = name.replace(['.', '_'], " ");
+        let tokens = normalized.split_whitespace().collect::<Vec<_>>();
+
+        // The last year-like token wins, so titles such as `1917` or
+        // `Blade Runner 2049` keep their number
+        let year_idx = (1..tokens.len())
+            .rev()
+            .find(|&i| Self::as_year(tokens[i]).is_some())?;
+
+        let year = Self::as_year(tokens[year_idx])?;
+        let title = tokens[..year_idx]
+            .join(" ")
+            .trim_end_matches(['-', '(', '[', ' '])
+            .to_string();
+
+        if title.is_empty() {
+            return None;
+        }
+
+        let rest = &tokens[year_idx + 1..];
+        let rest_str = rest.join(" ");
+
+        let edition = EDITION_RE
+            .find(&rest_str)
+            .map(|m| edition::capitalize(m.as_str()));
+
+        let source = match rest
+            .iter()
+            .any(|t| t.to_ascii_uppercase().contains("REMUX"))
+        {
+            true => Some(Source::Remux),
+            false => rest
+                .iter()
+                .map(|t| Source::from(Self::strip_group(t)))
+                .find(|s| !matches!(s, Source::Other(_))),
+        };
+
+        let resolution = rest.iter().find_map(|t| {
+            match Self::strip_group(t).to_ascii_lowercase().as_str() {
+                "2160p" | "4k" | "uhd" => Some(Resolution::UHD4K),
+                "1080p" | "1080i" => Some(Resolution::HD1080),
+                "720p" => Some(Resolution::HD720),
+                "480p" | "576p" => Some(Resolution::SD),
+                _ => None,
+            }
+        });
+
+        let group = match rest.is_empty() {
+            true => None,
+            false => GROUP_RE
+                .captures(name)
+                .map(|c| c["group"].to_string())
+                .filter(|g| !g.eq_ignore_ascii_case("DL") && Self::as_year(g).is_none()),
+        };
+
+        Some(Release {
+            title,
+            year,
+            edition,
+            source,
+            resolution,
+            group,
+        })
+    }
+
+    fn as_year(token: &str) -> Option<i16> {
+        let token = token.trim_matches(['(', ')', '[', ']']);
+        match token.len() == 4 && (token.starts_with("19") || token.starts_with("20")) {
+            true => token.parse().ok(),
+            false => None,
+        }
+    }
+
+    /// `x265-GROUP` -> `x265`, so the last token can still be inspected
+    fn strip_group(token: &str) -> &str {
+        match token.rsplit_once('-') {
+            Some((head, _)) if !head.eq_ignore_ascii_case("WEB") => head,
+            _ => token,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_release_names() {
+        // name, title, year, edition, source, resolution, group
+        type Case = (&'static str, &'static str, i16, Option<&'static str>, Option<Source>, Option<&'static str>, Option<&'static str>);
+        let cases: [Case; 7] = [
+            (
+                "Movie.Title.2019.1080p.BluRay.x265-GROUP",
+                "Movie Title", 2019, None, Some(Source::BluRay), Some("1080p"), Some("GROUP"),
+            ),
+            (
+                "1917.2019.2160p.UHD.BluRay.REMUX.HDR.HEVC-FGT",
+                "1917", 2019, None, Some(Source::Remux), Some("2160p"), Some("FGT"),
+            ),
+            (
+                "Blade.Runner.2049.2017.Final.Cut.1080p.WEB-DL.DDP5.1-NTb",
+                "Blade Runner 2049", 2017, Some("Final Cut"), Some(Source::WebDL), Some("1080p"), Some("NTb"),
+            ),
+            ("The Matrix (1999)", "The Matrix", 1999, None, None, None, None),
+            ("Some.Movie.2020.WEB-DL", "Some Movie", 2020, None, Some(Source::WebDL), None, None),
+            ("Movie_Name_2005_DVDRip", "Movie Name", 2005, None, Some(Source::DVD), None, None),
+            ("Heat.1995.720p.HDTV-[rarbg]", "Heat", 1995, None, Some(Source::HDTV), Some("720p"), None),
+        ];
+
+        for (name, title, year, edition, source, resolution, group) in cases {
+            let release = Release::parse(name).unwrap_or_else(|| panic!("{name} did not parse"));
+            assert_eq!(release.title, title, "{name}");
+            assert_eq!(release.year, year, "{name}");
+            assert_eq!(release.edition.as_deref(), edition, "{name}");
+            assert_eq!(release.source, source, "{name}");
+            assert_eq!(release.resolution.map(|r| r.to_string()).as_deref(), resolution, "{name}");
+            assert_eq!(release.group.as_deref(), group, "{name}");
+        }
+    }
+
+    #[test]
+    fn names_without_title_and_year_do_not_parse() {
+        for name in ["No year here", "2019", "2019.1080p.BluRay", ""] {
+            assert!(Release::parse(name).is_none(), "{name}");
+        }
+    }
+}
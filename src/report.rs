@@ -0,0 +1,468 @@
+//! What the numov binary prints: progress bars, tables and summaries of what the library returns
+
+use numov::{BackupPlan, Config, Library, PlannedFilm, Query, Result, ScanEvent, ScanReport, Side};
+use polars::prelude::*;
+use std::{
+    io::Stdout,
+    iter::repeat_n,
+    time::{Duration, Instant},
+};
+
+/// Handles the events of a library task: progress as a bar named after the
+/// task, and everything else as a line of its own
+pub struct Events {
+    job: &'static str,
+    prog: Option<Prog>,
+}
+
+impl Events {
+    pub fn new(job: &'static str) -> Self {
+        Events { job, prog: None }
+    }
+
+    pub fn handle(&mut self, event: ScanEvent) {
+        match event {
+            ScanEvent::Progress { done, total } => {
+                let prog = self.prog.get_or_insert_with(|| Prog::new(total, self.job));
+                prog.pb.set(done as u64);
+            }
+            ScanEvent::Skipped(e) => println!("Could not read {e}"),
+            ScanEvent::Warning(e) => println!("{e}"),
+            ScanEvent::TitleWritten { title, .. } => println!("Wrote title to metadata of file. [{title}]"),
+            ScanEvent::Renamed { from, to } => println!(
+                "\n\t\t{}\n\t\t==>\t{}",
+                from.file_name().unwrap_or_default().to_string_lossy(),
+                to.file_name().unwrap_or_default().to_string_lossy(),
+            ),
+            ScanEvent::Rescanned(report) => changes(report),
+        }
+    }
+
+    /// Finish the progress bar, if the task made one
+    pub fn end(self) {
+        if let Some(mut prog) = self.prog {
+            prog.end();
+        }
+    }
+}
+
+struct Prog {
+    pb: pbr::ProgressBar<Stdout>,
+    t1: Instant,
+    job: String,
+}
+
+impl Prog {
+    fn new(total: usize, job: &str) -> Self {
+        Prog {
+            pb: pbr::ProgressBar::new(total as u64),
+            t1: Instant::now(),
+            job: job.to_string(),
+        }
+    }
+
+    fn end(&mut self) {
+        let output = format!(
+            "\tFinished {} in {:.4?}",
+            self.job,
+            Instant::now() - self.t1
+        );
+        self.pb.finish_println(&output);
+        println!();
+    }
+}
+
+/// Print a report table with the `[tables]` report limits, then go back to the usual ones
+fn table(config: &Config, df: &DataFrame) {
+    config.tables.apply_report();
+    println!("{df}");
+    config.tables.apply();
+}
+
+/// Print the movies added, removed and changed, side by side
+pub fn changes(report: &ScanReport) {
+    let columns = [
+        ("New", &report.added),
+        ("Removed", &report.removed),
+        ("Updated", &report.updated),
+    ];
+    let max_len = columns.iter().map(|(_, names)| names.len()).max().unwrap_or_default();
+
+    let output_vec = columns
+        .into_iter()
+        .filter(|(_, names)| !names.is_empty())
+        .map(|(name, names)| {
+            let mut vec = names.clone();
+            vec.extend(repeat_n(String::new(), max_len - names.len()));
+            Series::new(&format!("{name} ({})", names.len()), vec)
+        })
+        .collect::<Vec<_>>();
+    if output_vec.is_empty() {
+        return;
+    }
+
+    match DataFrame::new(output_vec) {
+        Ok(df) => println!("{:?}", df),
+        Err(e) => println!("Could not create dataframe for updated values.\nError: {e}"),
+    }
+}
+
+/// Scan the library with a progress bar, and print what changed
+pub fn scan(lib: &mut Library, tv: bool) -> Result<()> {
+    let mut events = Events::new(match tv {
+        true => "updated tv library",
+        false => "updated library",
+    });
+    let report = match tv {
+        true => lib.scan_tv(|e| events.handle(e)),
+        false => lib.scan(|e| events.handle(e)),
+    }?;
+    events.end();
+
+    changes(&report);
+    if tv {
+        return Ok(());
+    }
+    let removed = report.extras_removed;
+    if report.extras_saved > 0 || removed > 0 {
+        println!("Updated {} extras, removed {removed}.", report.extras_saved);
+    }
+    println!(
+        "Library holds {} works in {} versions.",
+        lib.work_count(),
+        lib.movies().count()
+    );
+    Ok(())
+}
+
+/// Store the checksums hashed so far, and say how many files are left
+pub fn finish_checksums(lib: &mut Library) -> Result<()> {
+    let mut events = Events::new("hashing files");
+    let pending = lib.finish_checksums(|e| events.handle(e))?;
+    if pending > 0 {
+        println!("{pending} files are not hashed yet; the next scan or fsck will hash them.");
+    }
+    Ok(())
+}
+
+/// Scan the library, then rescan what changes until interrupted
+pub fn watch(lib: &mut Library) -> Result<()> {
+    scan(lib, false)?;
+    println!("Watching {} for changes (Ctrl-C to stop)", lib.root.display());
+    let mut events = Events::new("rescanning");
+    lib.watch(|e| events.handle(e))
+}
+
+/// Rename the movie folders, printing each rename
+pub fn rename(lib: &mut Library) -> Result<()> {
+    let mut events = Events::new("renaming folders");
+    let renamed = lib.rename_folders(|e| events.handle(e))?;
+    if renamed > 0 {
+        println!("\nRenamed {renamed} paths!");
+    }
+    Ok(())
+}
+
+/// Scrape a letterboxd user's ratings with a progress bar
+pub fn ratings(lib: &mut Library, user: &str) -> Result<()> {
+    let mut events = Events::new("scraping user ratings");
+    let report = lib.update_ratings(&user, |e| events.handle(e))?;
+    events.end();
+    match report.changed {
+        0 => println!("Ratings are up to date."),
+        changed => println!("ADDED {changed} RATINGS!"),
+    }
+    if report.mapped > 0 {
+        println!("Successfully mapped ratings to {} movies.", report.mapped);
+    }
+    Ok(())
+}
+
+/// Print the movies matching `query`, as a table or JSON. Returns the number of matches.
+pub fn query(lib: &Library, query: &Query, json: bool) -> Result<usize> {
+    let movies = lib.query(query);
+    if json {
+        let rows = movies
+            .iter()
+            .map(|m| {
+                serde_json::json!({
+                    "title": m.title,
+                    "year": m.year,
+                    "edition": m.edition,
+                    "resolution": m.video.resolution.to_string(),
+                    "codec": m.video.codec.to_string(),
+                    "hdr": m.video.hdr.to_string(),
+                    "size_gb": m.size,
+                    "duration": m.duration,
+                    "rating": m.rating,
+                    "genres": m.genres,
+                    "collection": m.collection,
+                    "hash": format!("{:x}", m.hash),
+                })
+            })
+            .collect::<Vec<_>>();
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(movies.len());
+    }
+
+    if movies.is_empty() {
+        println!("No movies match.");
+        return Ok(0);
+    }
+    let column = |f: fn(&numov::Movie) -> String| movies.iter().map(|m| f(m)).collect::<Vec<_>>();
+    let df = DataFrame::new(vec![
+        Series::new("Title", column(|m| m.title.clone())),
+        Series::new("Year", movies.iter().map(|m| m.year as u32).collect::<Vec<_>>()),
+        Series::new("Edition", column(|m| m.edition.clone().unwrap_or_default())),
+        Series::new("Res", column(|m| m.video.resolution.to_string())),
+        Series::new("Codec", column(|m| m.video.codec.to_string())),
+        Series::new("HDR", column(|m| m.video.hdr.to_string())),
+        Series::new("Size", movies.iter().map(|m| m.size).collect::<Vec<_>>()),
+        Series::new("Stars", column(|m| m.rating.clone().unwrap_or_default())),
+    ])?;
+    table(lib.config(), &df);
+    Ok(movies.len())
+}
+
+/// Print the `stats` table named `name`
+pub fn stats(lib: &Library, name: &str) -> Result<()> {
+    let df = lib.stats(name)?;
+    if name == "full" {
+        std::env::set_var("POLARS_FMT_MAX_COLS", "10");
+        std::env::set_var("POLARS_FMT_MAX_ROWS", lib.config().tables.report_rows.to_string());
+    }
+    println!("{:?}", df);
+    Ok(())
+}
+
+/// Write nfo files, or list those that would be written
+pub fn export_nfo(lib: &Library, policy: numov::Overwrite, dry_run: bool) -> Result<()> {
+    let export = lib.export_nfo(policy, dry_run)?;
+    let verb = match dry_run {
+        true => "Would write",
+        false => "Wrote",
+    };
+    if dry_run {
+        for path in &export.written {
+            println!("{verb} {}", path.display());
+        }
+    }
+    println!("{verb} {} nfo files ({} existing files kept).", export.written.len(), export.kept);
+    Ok(())
+}
+
+/// Print the layout problems, as a table or JSON. Returns how many there are.
+pub fn lint(lib: &Library, json: bool) -> Result<usize> {
+    let issues = lib.lint()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&issues)?);
+    } else if issues.is_empty() {
+        println!("No problems found.");
+    } else {
+        let df = DataFrame::new(vec![
+            Series::new("Problem", issues.iter().map(|i| i.problem.to_string()).collect::<Vec<_>>()),
+            Series::new("Path", issues.iter().map(|i| i.path.display().to_string()).collect::<Vec<_>>()),
+        ])?;
+        table(lib.config(), &df);
+    }
+    Ok(issues.len())
+}
+
+/// Verify the library's files and print the damaged ones. Returns how many are damaged.
+pub fn verify(lib: &mut Library, json: bool) -> Result<usize> {
+    let mut events = Events::new("verifying files");
+    let report = lib.verify(|e| events.handle(e))?;
+    events.end();
+
+    let checked = report.results.len() + report.unscanned.len();
+    let damaged = report.damaged().collect::<Vec<_>>();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&damaged)?);
+        return Ok(damaged.len());
+    } else if damaged.is_empty() {
+        println!("All {checked} movies look intact.");
+    } else {
+        let rows = damaged
+            .iter()
+            .flat_map(|v| v.problems.iter().map(|p| (&v.movie, p.as_str())))
+            .collect::<Vec<_>>();
+        let df = DataFrame::new(vec![
+            Series::new("Movie", rows.iter().map(|(m, _)| m.as_str()).collect::<Vec<_>>()),
+            Series::new("Problem", rows.iter().map(|(_, p)| *p).collect::<Vec<_>>()),
+        ])?;
+        table(lib.config(), &df);
+        println!("{} of {checked} movies have problems.", damaged.len());
+    }
+    if !report.unscanned.is_empty() {
+        println!(
+            "{} of these are not scanned yet; they were checked, but their results are not stored.",
+            report.unscanned.len()
+        );
+    }
+    Ok(damaged.len())
+}
+
+/// Hash the library's files again and print those whose content changed. Returns how many did.
+pub fn fsck(lib: &mut Library, json: bool) -> Result<usize> {
+    let mut events = Events::new("checking files");
+    let report = lib.fsck(|e| events.handle(e))?;
+    events.end();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report.changed)?);
+        return Ok(report.changed.len());
+    }
+    if !report.changed.is_empty() {
+        table(lib.config(), &DataFrame::new(vec![Series::new("Content changed", &report.changed)])?);
+    }
+    println!(
+        "Checked {} files: {} changed without a new size or mtime.",
+        report.checked,
+        report.changed.len()
+    );
+    if report.first > 0 {
+        println!(
+            "Stored a first checksum for {} files that had none for their size and mtime.",
+            report.first
+        );
+    }
+    Ok(report.changed.len())
+}
+
+/// Compare two roots and print how they differ. Returns the number of films that do.
+pub fn compare(lib: &mut Library, a: std::path::PathBuf, b: std::path::PathBuf, json: bool) -> Result<usize> {
+    let mut events = Events::new("comparing roots");
+    let comparison = lib.compare(a, b, |e| events.handle(e))?;
+    let differences = &comparison.differences;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(differences)?);
+        return Ok(differences.len());
+    }
+    if !differences.is_empty() {
+        let side = |s: &Option<Side>| {
+            s.as_ref()
+                .map_or("-".to_string(), |s| format!("{} ({:.2} GB, {})", s.path, s.size, s.video))
+        };
+        let df = DataFrame::new(vec![
+            Series::new("Status", differences.iter().map(|d| d.status.to_string()).collect::<Vec<_>>()),
+            Series::new("Movie", differences.iter().map(|d| d.movie.as_str()).collect::<Vec<_>>()),
+            Series::new("A", differences.iter().map(|d| side(&d.a)).collect::<Vec<_>>()),
+            Series::new("B", differences.iter().map(|d| side(&d.b)).collect::<Vec<_>>()),
+        ])?;
+        table(lib.config(), &df);
+    }
+    println!(
+        "{} films are identical on both sides, {} differ.",
+        comparison.identical,
+        differences.len()
+    );
+    Ok(differences.len())
+}
+
+/// Make a backup plan and print it
+pub fn plan_backup(lib: &mut Library, capacities: &[f32], by_rating: bool, dry_run: bool) -> Result<()> {
+    let Some(plan) = lib.plan_backup(capacities, by_rating, dry_run)? else {
+        println!("No backup drives given, and no stored plan to reuse.");
+        return Ok(());
+    };
+    let BackupPlan {
+        drives,
+        placed,
+        overflow,
+        unplaced,
+    } = &plan;
+
+    let df = DataFrame::new(vec![
+        Series::new("Drive", (1..=drives.len() as u32).collect::<Vec<_>>()),
+        Series::new("Capacity", drives.iter().map(|d| d.capacity).collect::<Vec<_>>()),
+        Series::new("Used", drives.iter().map(|d| d.used).collect::<Vec<_>>()),
+        Series::new("Free", drives.iter().map(|d| d.capacity - d.used).collect::<Vec<_>>()),
+        Series::new("Films", drives.iter().map(|d| d.films).collect::<Vec<_>>()),
+        Series::new("New", drives.iter().map(|d| d.new).collect::<Vec<_>>()),
+    ])?;
+    table(lib.config(), &df);
+
+    let drive = |film: &PlannedFilm| film.drive.map_or(0, |d| d as u32 + 1);
+    if placed.is_empty() {
+        println!("No new films to place.");
+    } else {
+        let df = DataFrame::new(vec![
+            Series::new("Drive", placed.iter().map(drive).collect::<Vec<_>>()),
+            Series::new("Movie", placed.iter().map(|f| f.movie.as_str()).collect::<Vec<_>>()),
+            Series::new("Size", placed.iter().map(|f| f.size).collect::<Vec<_>>()),
+            Series::new(
+                "Collection",
+                placed.iter().map(|f| f.collection.clone().unwrap_or_default()).collect::<Vec<_>>(),
+            ),
+        ])?;
+        table(lib.config(), &df);
+    }
+    if !overflow.is_empty() {
+        println!("{} films no longer fit on the drive their work is planned on:", overflow.len());
+        for film in overflow {
+            println!("\t{} ({:.2} GB, drive {})", film.movie, film.size, drive(film));
+        }
+    }
+    if !unplaced.is_empty() {
+        println!("{} films did not fit on any drive:", unplaced.len());
+        for film in unplaced {
+            println!("\t{} ({:.2} GB)", film.movie, film.size);
+        }
+    }
+    if dry_run {
+        println!("Dry run, the plan was not saved.");
+    }
+    Ok(())
+}
+
+/// Compare the probe with `Matroska::open` over the library's files, and print the numbers
+pub fn bench_probe(lib: &Library) -> Result<()> {
+    let mut events = Events::new("benchmarking probe");
+    let benches = lib.bench_probe(|e| events.handle(e))?;
+    if benches.is_empty() {
+        println!("No MKV files found.");
+        return Ok(());
+    }
+
+    let kb = |bytes: u64| bytes as f32 / 1024.0;
+    let ms = |time: Duration| time.as_secs_f32() * 1000.0;
+    let probe_kb = benches.iter().map(|b| kb(b.probe_bytes)).collect::<Vec<_>>();
+    let probe_ms = benches.iter().map(|b| ms(b.probe_time)).collect::<Vec<_>>();
+    let open_kb = benches.iter().map(|b| kb(b.open_bytes)).collect::<Vec<_>>();
+    let open_ms = benches.iter().map(|b| ms(b.open_time)).collect::<Vec<_>>();
+
+    let sum = |v: &[f32]| v.iter().sum::<f32>();
+    let files = benches.len() as f32;
+    println!(
+        "Probe:          {:>10.1} KB, {:>8.3} ms per file\nMatroska::open: {:>10.1} KB, {:>8.3} ms per file",
+        sum(&probe_kb) / files,
+        sum(&probe_ms) / files,
+        sum(&open_kb) / files,
+        sum(&open_ms) / files,
+    );
+    println!(
+        "The probe read {:.1}x fewer bytes and was {:.1}x faster over {} files.\n",
+        sum(&open_kb) / sum(&probe_kb),
+        sum(&open_ms) / sum(&probe_ms),
+        benches.len()
+    );
+
+    let df = DataFrame::new(vec![
+        Series::new("File", benches.iter().map(|b| b.file.as_str()).collect::<Vec<_>>()),
+        Series::new("Probe KB", probe_kb),
+        Series::new("Probe ms", probe_ms),
+        Series::new("Open KB", open_kb),
+        Series::new("Open ms", open_ms),
+    ])?;
+    table(lib.config(), &df);
+    Ok(())
+}
+
+/// Print the paths scans leave out, and why
+pub fn explain(lib: &Library) -> Result<()> {
+    for (path, reason) in lib.excluded()? {
+        println!("Skipped {}: {reason}", path.display());
+    }
+    Ok(())
+}
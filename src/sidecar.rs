@@ -0,0 +1,204 @@
+use crate::movie::SubtitleTrack;
+use crate::movie_types::sub_format::SubtitleFormat;
+use std::path::{Path, PathBuf};
+
+/// ISO 639-1 codes and English names for the languages seen most often in
+/// subtitle file names, mapped to the ISO 639-2 codes Matroska uses
+const LANGUAGES: &[(&str, &str, &str)] = &[
+    ("ar", "arabic", "ara"),
+    ("cs", "czech", "cze"),
+    ("da", "danish", "dan"),
+    ("de", "german", "ger"),
+    ("el", "greek", "gre"),
+    ("en", "english", "eng"),
+    ("es", "spanish", "spa"),
+    ("fi", "finnish", "fin"),
+    ("fr", "french", "fre"),
+    ("he", "hebrew", "heb"),
+    ("hi", "hindi", "hin"),
+    ("hu", "hungarian", "hun"),
+    ("it", "italian", "ita"),
+    ("ja", "japanese", "jpn"),
+    ("ko", "korean", "kor"),
+    ("nl", "dutch", "dut"),
+    ("no", "norwegian", "nor"),
+    ("pl", "polish", "pol"),
+    ("pt", "portuguese", "por"),
+    ("ro", "romanian", "rum"),
+    ("ru", "russian", "rus"),
+    ("sv", "swedish", "swe"),
+    ("th", "thai", "tha"),
+    ("tr", "turkish", "tur"),
+    ("uk", "ukrainian", "ukr"),
+    ("zh", "chinese", "chi"),
+];
+
+/// Subtitle format of a sidecar file, by extension
+fn format(path: &Path) -> Option<SubtitleFormat> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+        "srt" => Some(SubtitleFormat::SRT),
+        "ass" => Some(SubtitleFormat::ASS),
+        "ssa" => Some(SubtitleFormat::SSA),
+        "sup" => Some(SubtitleFormat::PGS),
+        "idx" => Some(SubtitleFormat::VOB),
+        _ => None,
+    }
+}
+
+/// ISO 639-2 code for a language tag in a file name (`en`, `eng`, `English`)
+fn language(tag: &str) -> Option<String> {
+    let tag = tag.to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(short, name, long)| tag == *short || tag == *name || tag == *long)
+        .map(|(_, _, long)| long.to_string())
+}
+
+/// `name` without `prefix`, compared without case. Lowercasing can change how many
+/// bytes and characters a name takes, so `name` is lowercased a character at a time
+/// until it has covered the lowercased prefix.
+fn strip_prefix_ignore_case<'a>(name: &'a str, prefix: &str) -> Option<&'a str> {
+    let prefix = prefix.to_lowercase();
+    let mut lower = String::new();
+    for (i, c) in name.char_indices() {
+        if lower == prefix {
+            return Some(&name[i..]);
+        }
+        lower.extend(c.to_lowercase());
+        if !prefix.starts_with(&lower) {
+            return None;
+        }
+    }
+    (lower == prefix).then_some("")
+}
+
+/// Parse the tags between the video's name and the extension:
+/// `Movie.en.forced.srt` is an English forced track
+pub fn parse(path: &Path, video_stem: &str) -> Option<SubtitleTrack> {
+    let format = format(path)?;
+    let file = path.file_name()?.to_str()?;
+    let stem = path.file_stem()?.to_str()?;
+    let tags = strip_prefix_ignore_case(stem, video_stem)?;
+
+    let mut language_tag = None;
+    let mut forced = false;
+    for tag in tags.split(['.', '_', ' ', '-']).filter(|t| !t.is_empty()) {
+        match tag.to_lowercase().as_str() {
+            "forced" | "foreign" => forced = true,
+            _ => language_tag = language_tag.or_else(|| language(tag)),
+        }
+    }
+
+    Some(SubtitleTrack {
+        format,
+        language: language_tag.unwrap_or_else(|| "und".to_string()),
+        forced,
+        file: Some(file.to_string()),
+    })
+}
+
+/// Subtitle files next to the given video files, named after one of them
+pub fn find(videos: &[&PathBuf]) -> Vec<SubtitleTrack> {
+    let Some(dir) = videos.first().and_then(|v| v.parent()) else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let stems = videos.iter().filter_map(|v| v.file_stem()?.to_str()).collect::<Vec<_>>();
+
+    let mut files = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && format(p).is_some())
+        .collect::<Vec<_>>();
+    files.sort();
+
+    files
+        .iter()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?;
+            let stem = stems
+                .iter()
+                .find(|s| strip_prefix_ignore_case(name, s).is_some_and(|rest| rest.starts_with('.')))?;
+            parse(path, stem)
+        })
+        .collect()
+}
+
+/// Whether an external track repeats the language and forced flag of an embedded one
+pub fn is_duplicate(track: &SubtitleTrack, tracks: &[SubtitleTrack]) -> bool {
+    tracks
+        .iter()
+        .any(|t| t.file.is_none() && t.language == track.language && t.forced == track.forced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `find` on empty files named `names`, next to the first of `videos`
+    fn find_in(dir: &str, videos: &[&str], names: &[&str]) -> Vec<(String, String, bool)> {
+        let dir = std::env::temp_dir().join(format!("numov-sidecar-{dir}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in videos.iter().chain(names) {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        let videos = videos.iter().map(|v| dir.join(v)).collect::<Vec<_>>();
+        let tracks = find(&videos.iter().collect::<Vec<_>>());
+        std::fs::remove_dir_all(&dir).unwrap();
+        tracks
+            .into_iter()
+            .map(|t| (t.file.unwrap_or_default(), t.language, t.forced))
+            .collect()
+    }
+
+    #[test]
+    fn tags_after_the_video_name_give_language_and_forced() {
+        let found = find_in(
+            "tags",
+            &["Heat (1995).mkv"],
+            &["Heat (1995).en.srt", "heat (1995).German.forced.sup", "Heat (1995).srt", "Heat.en.srt", "Heat (1995).nfo"],
+        );
+        assert_eq!(
+            found,
+            [
+                ("Heat (1995).en.srt".to_string(), "eng".to_string(), false),
+                ("Heat (1995).srt".to_string(), "und".to_string(), false),
+                ("heat (1995).German.forced.sup".to_string(), "ger".to_string(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn non_ascii_names_pair_in_any_case() {
+        // 'ẞ' lowercases to 'ß', one byte shorter, and 'İ' to "i̇", one byte longer
+        let found = find_in(
+            "unicode",
+            &["STRAẞE (2001).mkv", "İSTANBUL (2005).mkv"],
+            &["straße (2001).fr.srt", "i̇stanbul (2005).tr.forced.srt", "Amélie (2001).fr.srt"],
+        );
+        assert_eq!(
+            found,
+            [
+                ("i̇stanbul (2005).tr.forced.srt".to_string(), "tur".to_string(), true),
+                ("straße (2001).fr.srt".to_string(), "fre".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicates_repeat_an_embedded_track() {
+        let track = |language: &str, forced, file: Option<&str>| SubtitleTrack {
+            format: SubtitleFormat::SRT,
+            language: language.to_string(),
+            forced,
+            file: file.map(str::to_string),
+        };
+        let embedded = [track("eng", false, None), track("ger", true, None)];
+
+        assert!(is_duplicate(&track("eng", false, Some("a.en.srt")), &embedded));
+        assert!(!is_duplicate(&track("eng", true, Some("a.en.forced.srt")), &embedded));
+        assert!(!is_duplicate(&track("fre", false, Some("a.fr.srt")), &embedded));
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{error, movie::Movie};
+use regex::Regex;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
+use xxhash_rust::const_xxh32::xxh32;
+
+/// `Movie.cd1`, `Movie - part2`, `Movie_disc1`, `Movie pt2`, ...
+static PART_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?P<base>.*?)(?:^|[ ._-]+)(?:cd|dvd|disc|disk|part|pt)[ ._-]?(?P<part>\d{1,2})$")
+        .unwrap()
+});
+
+/// Name without the part marker, and the part number
+pub fn part_number(path: &Path) -> Option<(String, u8)> {
+    let stem = path.file_stem()?.to_str()?;
+    let captures = PART_RE.captures(stem)?;
+    Some((captures["base"].to_lowercase(), captures["part"].parse().ok()?))
+}
+
+/// Split the files of one folder into units: stacked parts of the same
+/// film are grouped (in part order), every other file stands alone
+pub fn group<'a>(paths: impl IntoIterator<Item = &'a PathBuf>) -> Vec<Vec<&'a PathBuf>> {
+    let mut stacks: BTreeMap<String, Vec<(u8, &PathBuf)>> = BTreeMap::new();
+    let mut units = Vec::new();
+
+    for path in paths {
+        match part_number(path) {
+            Some((base, part)) => stacks.entry(base).or_default().push((part, path)),
+            None => units.push(vec![path]),
+        }
+    }
+
+    for mut parts in stacks.into_values() {
+        parts.sort_by_key(|(part, _)| *part);
+        units.push(parts.into_iter().map(|(_, path)| path).collect());
+    }
+    units
+}
+
+/// Key of a unit: a single file keeps its own hash, a stack hashes its parts' hashes
+pub fn hash(unit: &[&PathBuf]) -> error::Result<u32> {
+    match unit {
+        [single] => Ok(Movie::read_metadata(single)?.1),
+        parts => {
+            let joined = parts
+                .iter()
+                .map(|p| Ok(Movie::read_metadata(p)?.1.to_string()))
+                .collect::<error::Result<Vec<_>>>()?
+                .join(",");
+            Ok(xxh32(joined.as_bytes(), 0))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part_numbers() {
+        let cases = [
+            ("Movie.cd1.mkv", Some(("movie", 1))),
+            ("Movie - part2.mkv", Some(("movie", 2))),
+            ("Movie_disc1.mkv", Some(("movie", 1))),
+            ("Movie pt2.mkv", Some(("movie", 2))),
+            ("Movie DVD 12.mkv", Some(("movie", 12))),
+            ("Movie.CD01.mkv", Some(("movie", 1))),
+            ("Movie.mkv", None),
+            ("Movie 2.mkv", None),
+            ("Apart 2.mkv", None),
+            ("Movie.cd123.mkv", None),
+        ];
+        for (name, expected) in cases {
+            let found = part_number(Path::new(name));
+            assert_eq!(found.as_ref().map(|(base, part)| (base.as_str(), *part)), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn stacks_are_grouped_in_part_order() {
+        let paths = ["Movie.cd2.mkv", "Other.mkv", "Movie.cd1.mkv", "Second.part1.mkv"].map(PathBuf::from);
+        let units = group(&paths)
+            .into_iter()
+            .map(|unit| unit.iter().map(|p| p.to_str().unwrap()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(units, [vec!["Other.mkv"], vec!["Movie.cd1.mkv", "Movie.cd2.mkv"], vec!["Second.part1.mkv"]]);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::{
+    error::{self, Error},
+    movie::{AudioStream, Movie, SubtitleStream, VideoStream},
+    pattern::NamePattern,
+    probe,
+};
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    sync::LazyLock,
+};
+
+/// `S01E02`, `s01e02-e03`, `S01E02E03` or `1x02`
+static EPISODE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(?:s(?P<season>\d{1,2})e(?P<episode>\d{1,3})(?:-?e(?P<last>\d{1,3}))?|\b(?P<alt_season>\d{1,2})x(?P<alt_episode>\d{2,3})\b)",
+    )
+    .unwrap()
+});
+
+#[derive(Debug)]
+pub struct Episode {
+    pub show: String,
+    pub show_year: Option<i16>,
+    pub season: u16,
+    pub episode: u16,
+    /// Last episode contained in the file, differs from `episode` for `S01E01-E02`
+    pub last_episode: u16,
+    pub title: Option<String>,
+    pub size: f32,
+    pub duration: String,
+    pub video: VideoStream,
+    pub audio: AudioStream,
+    pub subs: SubtitleStream,
+    pub hash: u32,
+}
+
+impl Episode {
+    /// Probe an episode file. `show_dir` is the `Show (Year)` folder it lives in.
+    pub fn new(path: &Path, show_dir: &Path, budget: u64) -> error::Result<Self> {
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let (season, episode, last_episode, title) =
+            Self::parse_numbers(stem).ok_or_else(|| Error::unusable(path, "no episode number in its name"))?;
+
+        let show_name = show_dir.file_name().and_then(|s| s.to_str()).unwrap_or_default();
+        let (show, show_year) = NamePattern::defaults()
+            .iter()
+            .find_map(|p| p.captures(show_name))
+            .map_or((show_name.to_string(), None), |info| {
+                (info.title, Some(info.year))
+            });
+
+        let matroska = probe::open(path, budget)?.matroska;
+        let (byte_count, hash) = Movie::read_metadata(path)?;
+        let duration = Movie::readable_duration(&matroska.info.duration.unwrap_or_default());
+        let video = Movie::get_video_stream(Movie::video_track(&matroska, path)?, path);
+        let (audio, subs) = Movie::process_tracks(&matroska.tracks);
+
+        Ok(Episode {
+            show,
+            show_year,
+            season,
+            episode,
+            last_episode,
+            title,
+            size: Movie::make_gb(byte_count),
+            duration,
+            video,
+            audio,
+            subs,
+            hash,
+        })
+    }
+
+    /// Season, first and last episode, and the episode title following the numbers
+    fn parse_numbers(stem: &str) -> Option<(u16, u16, u16, Option<String>)> {
+        let captures = EPISODE_RE.captures(stem)?;
+        let number = |a: &str, b: &str| {
+            captures
+                .name(a)
+                .or(captures.name(b))
+                .and_then(|m| m.as_str().parse::<u16>().ok())
+        };
+
+        let season = number("season", "alt_season")?;
+        let episode = number("episode", "alt_episode")?;
+        let last = number("last", "last").unwrap_or(episode).max(episode);
+
+        let title = stem[captures.get(0)?.end()..]
+            .trim_start_matches([' ', '-', '.', '_'])
+            .trim()
+            .to_string();
+
+        Some((season, episode, last, (!title.is_empty()).then_some(title)))
+    }
+
+    pub fn code(&self) -> String {
+        match self.last_episode > self.episode {
+            true => format!("S{:02}E{:02}-E{:02}", self.season, self.episode, self.last_episode),
+            false => format!("S{:02}E{:02}", self.season, self.episode),
+        }
+    }
+
+    pub fn display_name(&self) -> String {
+        format!("{} {}", self.show, self.code())
+    }
+}
+
+/// Summary of one season of a show
+#[derive(Debug)]
+pub struct SeasonReport {
+    pub show: String,
+    pub season: u16,
+    pub episodes: usize,
+    pub missing: Vec<u16>,
+    pub codecs: String,
+    pub resolutions: String,
+    pub size: f32,
+}
+
+impl SeasonReport {
+    pub fn build<'a>(episodes: impl IntoIterator<Item = &'a Episode>) -> Vec<SeasonReport> {
+        let mut seasons: BTreeMap<(&str, u16), Vec<&Episode>> = BTreeMap::new();
+        for ep in episodes {
+            seasons.entry((&ep.show, ep.season)).or_default().push(ep);
+        }
+
+        seasons
+            .into_iter()
+            .map(|((show, season), eps)| {
+                let present = eps
+                    .iter()
+                    .flat_map(|e| e.episode..=e.last_episode)
+                    .collect::<BTreeSet<_>>();
+                let max = present.last().copied().unwrap_or(0);
+                let missing = (1..max).filter(|n| !present.contains(n)).collect();
+
+                let join = |values: BTreeSet<String>| values.into_iter().collect::<Vec<_>>().join("/");
+
+                SeasonReport {
+                    show: show.to_string(),
+                    season,
+                    episodes: present.len(),
+                    missing,
+                    codecs: join(eps.iter().map(|e| e.video.codec.to_string()).collect()),
+                    resolutions: join(eps.iter().map(|e| e.video.resolution.to_string()).collect()),
+                    size: eps.iter().map(|e| e.size).sum(),
+                }
+            })
+            .collect()
+    }
+
+    /// Missing episode numbers collapsed into ranges, ie. `3, 5-7`
+    pub fn missing_str(&self) -> String {
+        let mut ranges: Vec<(u16, u16)> = Vec::new();
+        for &n in &self.missing {
+            match ranges.last_mut() {
+                Some((_, end)) if *end + 1 == n => *end = n,
+                _ => ranges.push((n, n)),
+            }
+        }
+
+        ranges
+            .iter()
+            .map(|&(start, end)| match start == end {
+                true => start.to_string(),
+                false => format!("{start}-{end}"),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn episode_numbers() {
+        let cases = [
+            ("S01E02", Some((1, 2, 2, None))),
+            ("Show.S01E02.Pilot", Some((1, 2, 2, Some("Pilot")))),
+            ("s1e5 - The Title", Some((1, 5, 5, Some("The Title")))),
+            ("S02E03-E04 - Double", Some((2, 3, 4, Some("Double")))),
+            ("S02E03E04", Some((2, 3, 4, None))),
+            ("S02E05-E04", Some((2, 5, 5, None))),
+            ("S10E100", Some((10, 100, 100, None))),
+            ("1x02 Title", Some((1, 2, 2, Some("Title")))),
+            ("Show 12x103", Some((12, 103, 103, None))),
+            ("1920x1080", None),
+            ("Episode 2", None),
+        ];
+        for (stem, expected) in cases {
+            let found = Episode::parse_numbers(stem);
+            let found = found.as_ref().map(|(s, e, l, t)| (*s, *e, *l, t.as_deref()));
+            assert_eq!(found, expected, "{stem}");
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use crate::{
+    movie::Movie,
+    probe::{self, CHAPTERS, CLUSTER, EBML, INFO, SEEKHEAD, SEGMENT, TAGS, TRACKS},
+};
+use matroska::MatroskaError;
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const CUES: u32 = 0x1C53BB6B;
+const ATTACHMENTS: u32 = 0x1941A469;
+const VOID: u32 = 0xEC;
+const CRC32: u32 = 0xBF;
+const TIMESTAMP_SCALE: u32 = 0x2AD7B1;
+const DURATION: u32 = 0x4489;
+const TIMESTAMP: u32 = 0xE7;
+const CUE_POINT: u32 = 0xBB;
+const CUE_TRACK_POSITIONS: u32 = 0xB7;
+const CUE_CLUSTER_POSITION: u32 = 0xF1;
+
+/// How far the last cluster may start from the declared duration. Clusters
+/// usually hold a few seconds, so a bigger gap means the file was cut short.
+const SLACK: Duration = Duration::from_secs(30);
+
+/// The outcome of verifying a movie's files
+#[derive(Debug, Serialize)]
+pub struct Verification {
+    /// Hash of the movie, see `Movie::read_metadata`
+    pub hash: u32,
+    /// Display name of the movie, or the file's path under the root if it is not scanned yet
+    pub movie: String,
+    /// What is wrong with its files; empty if nothing is
+    pub problems: Vec<String>,
+    /// Seconds since the Unix epoch
+    pub verified_at: u64,
+}
+
+impl Verification {
+    /// The outcome of verifying a movie now
+    pub fn new(hash: u32, movie: String, problems: Vec<String>) -> Self {
+        let verified_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Verification {
+            hash,
+            movie,
+            problems,
+            verified_at,
+        }
+    }
+
+    /// Whether no problems were found
+    pub fn ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+fn name(id: u32) -> &'static str {
+    match id {
+        SEEKHEAD => "SeekHead",
+        INFO => "Info",
+        TRACKS => "Tracks",
+        CHAPTERS => "Chapters",
+        CLUSTER => "Cluster",
+        CUES => "Cues",
+        ATTACHMENTS => "Attachments",
+        TAGS => "Tags",
+        _ => "element",
+    }
+}
+
+fn is_top_level(id: u32) -> bool {
+    matches!(id, SEEKHEAD | INFO | TRACKS | CHAPTERS | CLUSTER | CUES | ATTACHMENTS | TAGS | VOID | CRC32)
+}
+
+/// Check a Matroska file for damage: its EBML structure, that the SeekHead and
+/// Cues point at elements that are really there, that the file is as long as its
+/// Segment says, and that the last cluster starts about where the duration ends.
+pub fn check(path: &Path) -> Vec<String> {
+    match walk(path) {
+        Ok(problems) => problems,
+        Err(e) => vec![format!("Could not read file: {e}")],
+    }
+}
+
+fn walk(path: &Path) -> Result<Vec<String>, MatroskaError> {
+    let file_len = std::fs::metadata(path)?.len();
+    // Most reads are a cluster header and its timestamp, so keep the buffer small
+    let mut r = BufReader::with_capacity(256, File::open(path)?);
+    let mut problems = Vec::new();
+
+    let (id, size, len) = probe::element_header(&mut r)?;
+    if id != EBML {
+        return Ok(vec!["Not an EBML file".to_string()]);
+    }
+    let mut pos = len + size;
+    let (segment_start, segment_size) = loop {
+        r.seek(SeekFrom::Start(pos))?;
+        let (id, size, len) = probe::element_header(&mut r)?;
+        if id == SEGMENT {
+            break (pos + len, size);
+        }
+        pos += len + size;
+    };
+
+    let end = match segment_size {
+        u64::MAX => file_len,
+        size => {
+            let declared = segment_start + size;
+            if declared > file_len {
+                problems.push(format!(
+                    "File is {} bytes shorter than its Segment declares",
+                    declared - file_len
+                ));
+            } else if declared < file_len {
+                problems.push(format!("{} bytes of data after the Segment", file_len - declared));
+            }
+            declared.min(file_len)
+        }
+    };
+
+    let mut elements = HashSet::new();
+    let mut clusters = HashSet::new();
+    let mut seeks = Vec::new();
+    let mut cues = Vec::new();
+    let mut scale = 1_000_000;
+    let mut duration = None;
+    let mut last_timestamp = None;
+
+    let mut pos = segment_start;
+    while pos < end {
+        r.seek(SeekFrom::Start(pos))?;
+        let Ok((id, mut size, len)) = probe::element_header(&mut r) else {
+            problems.push(format!("Unreadable element header at byte {pos}"));
+            break;
+        };
+        if !is_top_level(id) {
+            problems.push(format!("Unexpected element {id:#X} at byte {pos}"));
+            break;
+        }
+        if size == u64::MAX {
+            if id != CLUSTER {
+                problems.push(format!("{} at byte {pos} has an unknown size", name(id)));
+                break;
+            }
+            // A header that reaches past the Segment leaves nothing to walk
+            size = unknown_cluster_size(&mut r, end.saturating_sub(pos + len))?;
+        }
+        if pos + len + size > file_len {
+            problems.push(format!("{} at byte {pos} runs past the end of the file", name(id)));
+            break;
+        }
+        elements.insert((id, pos));
+
+        match id {
+            CLUSTER => {
+                clusters.insert(pos);
+                if let Ok((TIMESTAMP, ts_size, _)) = probe::element_header(&mut r) {
+                    let mut buf = vec![0; ts_size.min(8) as usize];
+                    r.read_exact(&mut buf)?;
+                    last_timestamp = Some(probe::uint(&buf));
+                }
+            }
+            SEEKHEAD => seeks.extend(probe::seek_entries(&body(&mut r, size)?, segment_start)),
+            INFO => {
+                for (child, data) in probe::children(&body(&mut r, size)?) {
+                    match child {
+                        TIMESTAMP_SCALE => scale = probe::uint(data),
+                        DURATION => duration = float(data),
+                        _ => {}
+                    }
+                }
+            }
+            CUES => cues.extend(cue_positions(&body(&mut r, size)?, segment_start)),
+            _ => {}
+        }
+        pos += len + size;
+    }
+
+    for (id, at) in seeks {
+        if !elements.contains(&(id, at)) {
+            problems.push(format!("SeekHead points to {} at byte {at}, which is not there", name(id)));
+        }
+    }
+
+    let lost = cues.iter().filter(|p| !clusters.contains(p)).count();
+    if lost > 0 {
+        problems.push(format!("{lost} of {} cue points lead to no cluster", cues.len()));
+    }
+
+    match (duration, last_timestamp) {
+        (_, None) => problems.push("No clusters".to_string()),
+        (Some(duration), Some(last)) => {
+            let duration = Duration::from_nanos((duration * scale as f64) as u64);
+            let last = Duration::from_nanos(last.saturating_mul(scale));
+            if duration.abs_diff(last) > SLACK {
+                problems.push(format!(
+                    "Last cluster starts at {}, but the duration is {}",
+                    Movie::readable_duration(&last),
+                    Movie::readable_duration(&duration)
+                ));
+            }
+        }
+        (None, Some(_)) => {}
+    }
+
+    Ok(problems)
+}
+
+fn body<R: Read>(r: &mut R, size: u64) -> Result<Vec<u8>, MatroskaError> {
+    let mut buf = vec![0; size as usize];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn float(data: &[u8]) -> Option<f64> {
+    match data.len() {
+        4 => Some(f32::from_be_bytes(data.try_into().ok()?) as f64),
+        8 => Some(f64::from_be_bytes(data.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+/// Size of a cluster written without one, found by walking its children
+/// up to the next top level element
+fn unknown_cluster_size<R: Read + Seek>(r: &mut R, max: u64) -> Result<u64, MatroskaError> {
+    let start = r.stream_position()?;
+    let mut size = 0;
+    while size < max {
+        let Ok((id, child, len)) = probe::element_header(r) else {
+            break;
+        };
+        if is_top_level(id) && id != VOID && id != CRC32 {
+            break;
+        }
+        size += len + child;
+        r.seek(SeekFrom::Start(start + size))?;
+    }
+    r.seek(SeekFrom::Start(start))?;
+    Ok(size.min(max))
+}
+
+/// Absolute positions of the clusters the Cues point to
+fn cue_positions(buf: &[u8], segment_start: u64) -> Vec<u64> {
+    probe::children(buf)
+        .filter(|(id, _)| *id == CUE_POINT)
+        .flat_map(|(_, point)| probe::children(point))
+        .filter(|(id, _)| *id == CUE_TRACK_POSITIONS)
+        .flat_map(|(_, positions)| probe::children(positions))
+        .filter(|(id, _)| *id == CUE_CLUSTER_POSITION)
+        .map(|(_, data)| segment_start + probe::uint(data))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ebml::{element, uint};
+
+    const SEEK: u32 = 0x4DBB;
+    const SEEK_ID: u32 = 0x53AB;
+    const SEEK_POSITION: u32 = 0x53AC;
+
+    /// Runs `check` on `bytes` written to a scratch file
+    fn check_bytes(name: &str, bytes: &[u8]) -> Vec<String> {
+        let path = std::env::temp_dir().join(format!("numov-verify-{name}-{}.mkv", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let problems = check(&path);
+        std::fs::remove_file(&path).unwrap();
+        problems
+    }
+
+    fn header() -> Vec<u8> {
+        element(EBML, &element(0x4282, b"matroska"))
+    }
+
+    /// Where the Segment's children start, as the SeekHead and Cues count from it
+    fn segment_start() -> u64 {
+        header().len() as u64 + 12
+    }
+
+    /// A file whose Segment holds `children`
+    fn file(children: &[Vec<u8>]) -> Vec<u8> {
+        [header(), element(SEGMENT, &children.concat())].concat()
+    }
+
+    /// Two hours long, in milliseconds
+    fn info() -> Vec<u8> {
+        element(INFO, &[uint(TIMESTAMP_SCALE, 1_000_000), element(DURATION, &7_200_000f64.to_be_bytes())].concat())
+    }
+
+    fn cluster(timestamp_ms: u64) -> Vec<u8> {
+        element(CLUSTER, &uint(TIMESTAMP, timestamp_ms))
+    }
+
+    fn seek_head(entries: &[(u32, u64)]) -> Vec<u8> {
+        let seeks = entries
+            .iter()
+            .map(|(id, at)| element(SEEK, &[uint(SEEK_ID, *id as u64), uint(SEEK_POSITION, *at)].concat()))
+            .collect::<Vec<_>>();
+        element(SEEKHEAD, &seeks.concat())
+    }
+
+    fn cues(positions: &[u64]) -> Vec<u8> {
+        let points = positions
+            .iter()
+            .map(|at| element(CUE_POINT, &element(CUE_TRACK_POSITIONS, &uint(CUE_CLUSTER_POSITION, *at))))
+            .collect::<Vec<_>>();
+        element(CUES, &points.concat())
+    }
+
+    #[test]
+    fn a_file_cut_short_is_shorter_than_its_segment() {
+        let whole = file(&[info(), cluster(7_199_000)]);
+        assert_eq!(check_bytes("whole", &whole), Vec::<String>::new());
+
+        let problems = check_bytes("cut", &whole[..whole.len() - 10]);
+        assert_eq!(problems[0], "File is 10 bytes shorter than its Segment declares");
+    }
+
+    #[test]
+    fn seek_entries_must_point_at_their_element() {
+        // The SeekHead holds two entries, so its length is known before the positions are
+        let info_at = seek_head(&[(INFO, 0), (CUES, 0)]).len() as u64;
+        let missing_at = info_at + info().len() as u64;
+        let children = [seek_head(&[(INFO, info_at), (CUES, missing_at)]), info(), cluster(7_199_000)];
+
+        let problems = check_bytes("seek", &file(&children));
+        let missing = segment_start() + missing_at;
+        assert_eq!(problems, [format!("SeekHead points to Cues at byte {missing}, which is not there")]);
+    }
+
+    #[test]
+    fn cue_points_must_lead_to_a_cluster() {
+        let cluster_at = info().len() as u64;
+        let children = [info(), cluster(7_199_000), cues(&[cluster_at, cluster_at + 1])];
+
+        let problems = check_bytes("cues", &file(&children));
+        assert_eq!(problems, ["1 of 2 cue points lead to no cluster"]);
+    }
+
+    #[test]
+    fn the_last_cluster_must_start_near_the_duration() {
+        let slack = SLACK.as_millis() as u64;
+        let near = file(&[info(), cluster(0), cluster(7_200_000 - slack + 1_000)]);
+        assert_eq!(check_bytes("near", &near), Vec::<String>::new());
+
+        let early = file(&[info(), cluster(0), cluster(7_200_000 - slack - 1_000)]);
+        let problems = check_bytes("early", &early);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("Last cluster starts at"), "{problems:?}");
+    }
+
+    #[test]
+    fn unknown_size_cluster_past_the_segment() {
+        let ebml = [0x1A, 0x45, 0xDF, 0xA3, 0x80];
+        // The Segment declares 2 bytes, but an unknown size Cluster header takes 12
+        let segment = [0x18, 0x53, 0x80, 0x67, 0x82];
+        let cluster = [0x1F, 0x43, 0xB6, 0x75, 0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+        let timestamp = [0xE7, 0x81, 0x00];
+
+        let cases: [(&str, &[&[u8]], &str); 2] = [
+            ("after", &[&ebml, &segment, &cluster, &timestamp], "13 bytes of data after the Segment"),
+            ("cut", &[&ebml, &segment, &cluster], "10 bytes of data after the Segment"),
+        ];
+        for (name, parts, expected) in cases {
+            let problems = check_bytes(name, &parts.concat());
+            assert!(problems.iter().any(|p| p == expected), "{name}: {problems:?}");
+        }
+    }
+}
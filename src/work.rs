@@ -0,0 +1,53 @@
+use crate::movie::Movie;
+use std::collections::HashMap;
+
+/// A film (title, year and edition) and every file version of it in the library
+#[derive(Debug)]
+pub struct Work<'a> {
+    pub title: &'a str,
+    pub year: i16,
+    pub edition: Option<&'a str>,
+    /// Best quality version first
+    pub versions: Vec<&'a Movie>,
+}
+
+impl<'a> Work<'a> {
+    /// Identifies the work a movie is a version of, whatever its file is named
+    pub fn key(movie: &Movie) -> String {
+        Self::key_of(&movie.title, movie.year, movie.edition.as_deref())
+    }
+
+    /// `key`, from the parts of a movie stored apart from it
+    pub(crate) fn key_of(title: &str, year: i16, edition: Option<&str>) -> String {
+        let edition = edition.unwrap_or_default();
+        format!("{} ({}) {{{}}}", title.to_lowercase(), year, edition.to_lowercase())
+    }
+
+    /// Group movies into works, sorted by title
+    pub fn group(movies: impl IntoIterator<Item = &'a Movie>) -> Vec<Work<'a>> {
+        let mut groups: HashMap<String, Vec<&Movie>> = HashMap::new();
+        for movie in movies {
+            groups.entry(Self::key(movie)).or_default().push(movie);
+        }
+
+        let mut works = groups
+            .into_values()
+            .map(|mut versions| {
+                versions.sort_by_key(|m| std::cmp::Reverse(m.quality_score()));
+                Work {
+                    title: &versions[0].title,
+                    year: versions[0].year,
+                    edition: versions[0].edition.as_deref(),
+                    versions,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        works.sort_by(|a, b| (a.title, a.year).cmp(&(b.title, b.year)));
+        works
+    }
+
+    pub fn best(&self) -> &'a Movie {
+        self.versions[0]
+    }
+}
@@ -0,0 +1,16 @@
+//! EBML builders for test files. The unit tests in `src` include this file too,
+//! so both build their Matroska files the same way.
+
+/// An EBML element with an 8 byte size
+pub fn element(id: u32, body: &[u8]) -> Vec<u8> {
+    let mut out = id.to_be_bytes().into_iter().skip_while(|&b| b == 0).collect::<Vec<_>>();
+    out.push(0x01);
+    out.extend_from_slice(&(body.len() as u64).to_be_bytes()[1..]);
+    out.extend_from_slice(body);
+    out
+}
+
+/// An unsigned integer element
+pub fn uint(id: u32, value: u64) -> Vec<u8> {
+    element(id, &value.to_be_bytes())
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use common::{element, uint};
+use numov::{Config, Database, Library, ScanEvent};
+use std::{
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+/// A two hour, 1080p HEVC Matroska file with a single cluster
+fn write_mkv(path: &Path, title: Option<&str>) {
+    let header = [uint(0x4286, 1), element(0x4282, b"matroska"), uint(0x4287, 4), uint(0x4285, 2)].concat();
+
+    let mut info = [
+        uint(0x2AD7B1, 1_000_000),
+        element(0x4489, &7_200_000f64.to_be_bytes()),
+        element(0x4D80, b"numov tests"),
+        element(0x5741, b"numov tests"),
+    ]
+    .concat();
+    if let Some(title) = title {
+        info.extend(element(0x7BA9, title.as_bytes()));
+    }
+
+    let video = [uint(0xB0, 1920), uint(0xBA, 1080)].concat();
+    let track = [
+        uint(0xD7, 1),
+        uint(0x73C5, 1),
+        uint(0x83, 1),
+        element(0x86, b"V_MPEGH/ISO/HEVC"),
+        element(0xE0, &video),
+    ]
+    .concat();
+    let cluster = [uint(0xE7, 0), element(0xA3, &[0x81, 0, 0, 0x80, 0, 0, 0, 0])].concat();
+
+    let segment = [
+        element(0x1549A966, &info),
+        element(0x1654AE6B, &element(0xAE, &track)),
+        element(0x1F43B675, &cluster),
+    ]
+    .concat();
+
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, [element(0x1A45DFA3, &header), element(0x18538067, &segment)].concat()).unwrap();
+}
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("numov-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn open(root: &Path, db: &Path) -> Library {
+    let mut config = Config::default();
+    config.scan.batch_size = 1;
+    Library::from_parts(root.to_path_buf(), Database::open_at(db).unwrap(), config).unwrap()
+}
+
+#[test]
+fn interrupted_scan_resumes_where_it_stopped() {
+    let dir = scratch("resume");
+    let (root, db) = (dir.join("movies"), dir.join("numov.db"));
+
+    // Folders are scanned in name order, so the unusable file comes first
+    write_mkv(&root.join("Broken").join("broken.mkv"), None);
+    let films = (1..=6).map(|i| format!("Film {i} (200{i})")).collect::<Vec<_>>();
+    for film in &films {
+        write_mkv(&root.join(film).join(format!("{film}.mkv")), Some(film));
+    }
+
+    // Killed while the third film is being reported, before it is saved
+    let mut lib = open(&root, &db);
+    let mut skipped = 0;
+    let interrupted = panic::catch_unwind(AssertUnwindSafe(|| {
+        lib.scan(|event| match event {
+            ScanEvent::Skipped(_) => skipped += 1,
+            ScanEvent::Progress { done: 4, .. } => panic!("scan interrupted"),
+            _ => {}
+        })
+        .unwrap()
+    }));
+    assert!(interrupted.is_err());
+    assert_eq!(skipped, 1, "the unusable file is skipped, not fatal");
+    drop(lib);
+
+    let mut lib = open(&root, &db);
+    assert_eq!(lib.movies().count(), 2, "films saved before the interruption");
+
+    let report = lib.scan(|_| {}).unwrap();
+    assert_eq!(report.added, films[2..]);
+    assert!(report.updated.is_empty() && report.removed.is_empty());
+    assert_eq!(lib.movies().count(), films.len());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn scanning_again_finds_only_what_changed() {
+    let dir = scratch("rescan");
+    let (root, db) = (dir.join("movies"), dir.join("numov.db"));
+    let films = (1..=3).map(|i| format!("Film {i} (200{i})")).collect::<Vec<_>>();
+    for film in &films {
+        write_mkv(&root.join(film).join(format!("{film}.mkv")), Some(film));
+    }
+
+    let mut lib = open(&root, &db);
+    assert_eq!(lib.scan(|_| {}).unwrap().added, films);
+
+    let report = lib.scan(|_| {}).unwrap();
+    assert!(report.added.is_empty() && report.updated.is_empty() && report.removed.is_empty());
+    assert_eq!(report.extras_saved + report.extras_removed, 0);
+
+    fs::remove_dir_all(root.join(&films[1])).unwrap();
+    let report = lib.scan(|_| {}).unwrap();
+    assert_eq!(report.removed, films[1..2]);
+    assert!(report.added.is_empty() && report.updated.is_empty());
+    assert_eq!(lib.movies().count(), 2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}